@@ -0,0 +1,32 @@
+use ink::primitives::AccountId;
+use ink::storage::traits::StorageLayout;
+
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub enum DebtAssignmentStatus {
+    /// Waiting for the consent of the new creditor
+    Proposed,
+    /// Used by the settlement plan until the debtor pays the new creditor
+    Accepted,
+}
+
+/// Each debt assignment redirects (part of) the debt of a member to a new creditor.
+/// The debtor pays the new creditor directly, instead of the member who assigned the debt.
+/// The amount is the part of the debt still pending to be paid.
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct DebtAssignment {
+    pub id: u32,
+    pub group_id: u128,
+    pub assigned_by: AccountId,
+    pub debtor: AccountId,
+    pub new_creditor: AccountId,
+    pub amount: u128,
+    pub status: DebtAssignmentStatus,
+}
+
+impl DebtAssignment {
+    pub fn is_accepted(&self) -> bool {
+        self.status == DebtAssignmentStatus::Accepted
+    }
+}
@@ -0,0 +1,90 @@
+use crate::recurring::RecurrenceUnit;
+use ink::prelude::{string::String, vec::Vec};
+use ink::storage::traits::StorageLayout;
+
+/// What happens when an expense exceeds the group budget.
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub enum BudgetEnforcement {
+    /// The expense is rejected
+    REJECT,
+    /// The expense is added and flagged with an event
+    FLAG,
+}
+
+/// The part of the budget a limit applies to.
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub enum BudgetScope {
+    Total,
+    Category(String),
+    Period,
+}
+
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct CategoryBudget {
+    pub category: String,
+    pub limit: u128,
+}
+
+/// A limit for the spending of every period.
+/// The period length and start are expressed in the recurrence unit.
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct PeriodBudget {
+    pub unit: RecurrenceUnit,
+    pub length: u64,
+    pub limit: u128,
+    pub current_period_start: u64,
+    pub current_period_spent: u128,
+}
+
+impl PeriodBudget {
+    /// Gets the start of the period that contains the specified moment.
+    pub fn period_start_at(&self, now: u64) -> u64 {
+        if now < self.current_period_start || self.length == 0 {
+            return self.current_period_start;
+        }
+
+        now - (now - self.current_period_start) % self.length
+    }
+
+    /// Moves to the period that contains the specified moment, restarting the spending if it changes.
+    pub fn roll_to(&mut self, now: u64) {
+        let period_start = self.period_start_at(now);
+        if period_start != self.current_period_start {
+            self.current_period_start = period_start;
+            self.current_period_spent = 0;
+        }
+    }
+
+    /// Gets the spending of the period that contains the specified moment.
+    pub fn spent_at(&self, now: u64) -> u128 {
+        if self.period_start_at(now) == self.current_period_start {
+            self.current_period_spent
+        } else {
+            0
+        }
+    }
+}
+
+/// The agreed budget of a group.
+/// Every limit is optional: a total, by category and by period.
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct GroupBudget {
+    pub total: Option<u128>,
+    pub categories: Vec<CategoryBudget>,
+    pub period: Option<PeriodBudget>,
+    pub enforcement: BudgetEnforcement,
+}
+
+impl GroupBudget {
+    pub fn category_limit(&self, category: &String) -> Option<u128> {
+        self.categories
+            .iter()
+            .find(|c| &c.category == category)
+            .map(|c| c.limit)
+    }
+}
@@ -0,0 +1,36 @@
+use ink::prelude::vec::Vec;
+use ink::primitives::AccountId;
+use ink::storage::traits::StorageLayout;
+
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub enum DelegatedAction {
+    AddExpense,
+    SettleUp,
+}
+
+/// Each delegation allows a delegate to act on behalf of a principal member.
+/// It is scoped to specific groups and actions, and optionally expires.
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct Delegation {
+    pub principal: AccountId,
+    pub delegate: AccountId,
+    pub group_ids: Vec<u128>,
+    pub actions: Vec<DelegatedAction>,
+    /// The block timestamp from which the delegation is no longer valid
+    pub expires_at: Option<u64>,
+}
+
+impl Delegation {
+    pub fn is_expired(&self, now: u64) -> bool {
+        match self.expires_at {
+            Some(expires_at) => now >= expires_at,
+            None => false,
+        }
+    }
+
+    pub fn allows(&self, group_id: u128, action: &DelegatedAction) -> bool {
+        self.group_ids.iter().any(|id| *id == group_id) && self.actions.iter().any(|a| a == action)
+    }
+}
@@ -0,0 +1,50 @@
+use crate::input_models::ExpenseInput;
+use ink::prelude::vec::Vec;
+use ink::primitives::AccountId;
+use ink::storage::traits::StorageLayout;
+
+/// How a disputed expense is resolved.
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub enum DisputeResolution {
+    /// The expense is kept as it is
+    Confirm,
+    /// The expense is replaced by a new one (same ID)
+    Amend(ExpenseInput),
+    /// The expense is kept in the history without effect on the balances
+    Delete,
+}
+
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct DisputeVote {
+    pub voter: AccountId,
+    pub resolution: DisputeResolution,
+}
+
+/// Each dispute contests an expense of a group, with the hash of the reason (kept off-chain).
+/// The dispute is resolved by a group admin or by the majority of the group members.
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct ExpenseDispute {
+    pub group_id: u128,
+    pub expense_id: u32,
+    pub disputed_by: AccountId,
+    pub reason_hash: [u8; 32],
+    pub votes: Vec<DisputeVote>,
+}
+
+impl ExpenseDispute {
+    /// Adds the vote of a member, replacing the previous one (if any).
+    pub fn add_vote(&mut self, voter: AccountId, resolution: DisputeResolution) {
+        self.votes.retain(|v| v.voter != voter);
+        self.votes.push(DisputeVote { voter, resolution });
+    }
+
+    pub fn count_votes(&self, resolution: &DisputeResolution) -> u32 {
+        self.votes
+            .iter()
+            .filter(|v| &v.resolution == resolution)
+            .count() as u32
+    }
+}
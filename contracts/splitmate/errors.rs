@@ -1,5 +1,9 @@
+use ink::prelude::string::String;
+use ink::storage::traits::StorageLayout;
+use openbrush::contracts::traits::psp22::PSP22Error;
+
 #[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
-#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
 pub enum ContractError {
     MemberIsNotInTheGroup,
     ExpenseDistributionMemberIsNotInTheGroup,
@@ -11,4 +15,96 @@ pub enum ContractError {
     TransferError,
     MemberDoesNotHaveGroups,
     TheGroupIsSettled,
+    NothingToSettle,
+    MemberIsNotTheNetDebtor,
+    MemberNotFound,
+    Overflow,
+    AmountTooLarge,
+    CallerIsNotGroupAdmin,
+    TransferInsufficientBalance,
+    TransferInsufficientAllowance,
+    TransferZeroRecipientAddress,
+    TransferZeroSenderAddress,
+    TransferSafeTransferCheckFailed(String),
+    TransferCustomError(String),
+    SettlementAmountIsZero,
+    SettlementTakerIsTheGiver,
+    SettlementExceedsGiverDebt,
+    SettlementExceedsTakerCredit,
+    GuestDoesNotExist,
+    InvalidInviteSignature,
+    MemberIsAlreadyInTheGroup,
+    MemberIsAGuest,
+    MigrationToTheSameAddress,
+    AccountRecoveryDoesNotExist,
+    AccountRecoveryIsAlreadyRequested,
+    AccountRecoveryAddressMismatch,
+    DelegationDoesNotExist,
+    DelegationIsExpired,
+    DelegationDoesNotAllowTheAction,
+    DelegateIsThePrincipal,
+    InvalidSignature,
+    InvalidNonce,
+    RecurringPeriodIsZero,
+    RecurringExpenseDoesNotExist,
+    CallerIsNotTheCreator,
+    ExpenseWithoutItems,
+    ExpenseItemAmountIsZero,
+    ExpenseItemWithoutMembers,
+    ExpenseItemDistributionDoesNotMatchAmount,
+    ExpenseItemsDoNotMatchAmount,
+    ExpenseDoesNotExist,
+    ExpenseCannotBeRefunded,
+    RefundExceedsExpenseAmount,
+    RefundWithoutDistribution,
+    SplitWithoutWeights,
+    RefundDistributionDoesNotMatchAmount,
+    MemberTransferToTheSameMember,
+    GroupDoesNotHaveBudget,
+    BudgetPeriodIsZero,
+    GroupBudgetExceeded,
+    PotContributionIsZero,
+    PotBalanceIsNotEnough,
+    PotContributionIsNotEnough,
+    CallerIsNotAPotPayer,
+    PotSpendDistributionDoesNotMatchAmount,
+    DebtAssignmentDoesNotExist,
+    DebtAssignmentIsAlreadyAccepted,
+    DebtAssignmentInvolvesTheAssigner,
+    CallerIsNotTheNewCreditor,
+    CallerIsNotTheNewCreditorOrTheAssigner,
+    AssignerIsNotOwedByTheDebtor,
+    ExpenseIsNotActive,
+    ExpenseDisputeDoesNotExist,
+    ExpenseCannotBeAmended,
+    ExpenseHasRefunds,
+    LoanPrincipalIsZero,
+    LoanWithoutInstalments,
+    LoanInstalmentPeriodIsZero,
+    LoanLenderIsTheBorrower,
+    LoanDoesNotExist,
+    LoanRepaymentIsZero,
+    LoanRepaymentExceedsOutstanding,
+    CallerIsNotTheBorrower,
+    AutoSettleIsNotEnabled,
+    DebtIsBelowAutoSettleThreshold,
+    MemberIsAlreadyAnAdmin,
+    MemberIsNotAnAdmin,
+    GroupMustHaveAnAdmin,
+    RecurringExpenseWithSettleBy,
+}
+
+impl From<PSP22Error> for ContractError {
+    fn from(error: PSP22Error) -> Self {
+        match error {
+            PSP22Error::InsufficientBalance => ContractError::TransferInsufficientBalance,
+            PSP22Error::InsufficientAllowance => ContractError::TransferInsufficientAllowance,
+            PSP22Error::ZeroRecipientAddress => ContractError::TransferZeroRecipientAddress,
+            PSP22Error::ZeroSenderAddress => ContractError::TransferZeroSenderAddress,
+            PSP22Error::SafeTransferCheckFailed(reason) => {
+                ContractError::TransferSafeTransferCheckFailed(reason)
+            }
+            PSP22Error::Custom(reason) => ContractError::TransferCustomError(reason),
+        }
+    }
 }
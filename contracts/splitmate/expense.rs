@@ -1,7 +1,7 @@
 use crate::errors::ContractError;
-use crate::input_models::ExpenseInput;
+use crate::input_models::{DistributionInput, ExpenseInput, MemberTransferInput};
 use crate::utils::BaseResult;
-use ink::prelude::vec::Vec;
+use ink::prelude::{string::String, vec::Vec};
 use ink::primitives::AccountId;
 use ink::storage::traits::StorageLayout;
 
@@ -12,6 +12,30 @@ pub enum DistributionType {
     UNEQUALLY,
 }
 
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub enum ExpenseKind {
+    EXPENSE,
+    /// Gives back (totally or partially) the amount of the original expense
+    REFUND {
+        original_expense_id: u32,
+    },
+    /// Moves money directly from a member (the payer) to another member (the only distribution member)
+    TRANSFER,
+}
+
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub enum ExpenseStatus {
+    ACTIVE,
+    /// Contested by a member, still affecting the balances
+    DISPUTED,
+    /// Contested by a member, without effect on the balances until the dispute is resolved
+    FROZEN,
+    /// Kept in the history without effect on the balances
+    DELETED,
+}
+
 #[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
 #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
 pub struct ExpenseMember {
@@ -22,21 +46,51 @@ pub struct ExpenseMember {
     pub must_pay: u128,
 }
 
-/// Each expense has an ID and is linked to a group.
+/// A line item of an itemized expense, distributed between its own members.
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct ExpenseItem {
+    pub name: String,
+    pub amount: u128,
+    pub distribution: DistributionInput,
+}
+
+/// A shared line of an itemized expense (E.g. tax or tip).
+/// Distributed proportionally to the member item totals.
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct ExpenseSharedLine {
+    pub name: String,
+    pub amount: u128,
+}
+
+/// Each expense has an ID, is linked to a group and keeps the member who created it.
 /// Two distribution types: equally and unequally.
 /// The total amount distribution is specified in the members list.
+/// Itemized expenses also keep their items and shared lines.
+/// Refunds keep the category of the original expense.
 #[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
 #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
 pub struct Expense {
     pub id: u32,
     pub group_id: u128,
+    pub kind: ExpenseKind,
+    pub status: ExpenseStatus,
+    pub created_by: AccountId,
     pub amount: u128,
     pub distribution_type: DistributionType,
     pub members: Vec<ExpenseMember>,
+    pub items: Vec<ExpenseItem>,
+    pub shared_lines: Vec<ExpenseSharedLine>,
+    pub category: Option<String>,
+    /// The block timestamp when the expense was added
+    pub created_at: u64,
+    /// The block timestamp before which the resulting debts should be settled
+    pub settle_by: Option<u64>,
 }
 
 impl Expense {
-    pub fn new(id: u32, expense_to_add: ExpenseInput) -> Expense {
+    pub fn new(id: u32, created_by: AccountId, expense_to_add: ExpenseInput) -> Expense {
         let members = expense_to_add
             .distribution
             .distribution_by_members
@@ -60,12 +114,72 @@ impl Expense {
         Expense {
             id,
             group_id: expense_to_add.group_id,
+            kind: ExpenseKind::EXPENSE,
+            status: ExpenseStatus::ACTIVE,
+            created_by,
             amount: expense_to_add.amount,
             distribution_type: expense_to_add.distribution.distribution_type,
             members,
+            items: Vec::<ExpenseItem>::new(),
+            shared_lines: Vec::<ExpenseSharedLine>::new(),
+            category: expense_to_add.category,
+            created_at: 0,
+            settle_by: expense_to_add.settle_by,
         }
     }
 
+    pub fn new_transfer(
+        id: u32,
+        created_by: AccountId,
+        transfer_to_add: MemberTransferInput,
+    ) -> Expense {
+        Expense {
+            id,
+            group_id: transfer_to_add.group_id,
+            kind: ExpenseKind::TRANSFER,
+            status: ExpenseStatus::ACTIVE,
+            created_by,
+            amount: transfer_to_add.amount,
+            distribution_type: DistributionType::UNEQUALLY,
+            members: [
+                ExpenseMember {
+                    address: transfer_to_add.from_address,
+                    paid: transfer_to_add.amount,
+                    must_pay: 0,
+                },
+                ExpenseMember {
+                    address: transfer_to_add.to_address,
+                    paid: 0,
+                    must_pay: transfer_to_add.amount,
+                },
+            ]
+            .to_vec(),
+            items: Vec::<ExpenseItem>::new(),
+            shared_lines: Vec::<ExpenseSharedLine>::new(),
+            category: None,
+            created_at: 0,
+            settle_by: transfer_to_add.settle_by,
+        }
+    }
+
+    pub fn is_transfer(&self) -> bool {
+        self.kind == ExpenseKind::TRANSFER
+    }
+
+    pub fn is_refund_of(&self, expense_id: u32) -> bool {
+        match self.kind {
+            ExpenseKind::REFUND {
+                original_expense_id,
+            } => original_expense_id == expense_id,
+            _ => false,
+        }
+    }
+
+    /// Checks if the expense is considered in the group balances.
+    pub fn affects_balances(&self) -> bool {
+        self.status != ExpenseStatus::FROZEN && self.status != ExpenseStatus::DELETED
+    }
+
     pub fn validate(&self) -> BaseResult {
         if self.amount == 0 {
             return Err(ContractError::ExpenseAmountIsZero);
@@ -75,6 +189,24 @@ impl Expense {
             return Err(ContractError::ExpenseWithoutDistributionMembers);
         }
 
+        if self.items.len() > 0 {
+            let mut items_amount: u128 = 0;
+            for line_amount in self
+                .items
+                .iter()
+                .map(|item| item.amount)
+                .chain(self.shared_lines.iter().map(|line| line.amount))
+            {
+                items_amount = items_amount
+                    .checked_add(line_amount)
+                    .ok_or(ContractError::Overflow)?;
+            }
+
+            if items_amount != self.amount {
+                return Err(ContractError::ExpenseItemsDoNotMatchAmount);
+            }
+        }
+
         // ToDo: Add the following validations ->
         // - Sum of payers must be equal to total_amount
         // - Sum of split.members.amount must be equal to total_amount
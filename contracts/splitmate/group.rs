@@ -1,3 +1,4 @@
+use crate::budget::GroupBudget;
 use ink::prelude::{string::String, vec::Vec};
 use ink::primitives::AccountId;
 use ink::storage::traits::StorageLayout;
@@ -9,25 +10,54 @@ pub struct GroupMember {
     pub name: String,
     /// The total member balance/debt
     pub debt_value: i128,
+    /// The group-local ID if the member is a guest without account
+    pub guest_id: Option<u32>,
+}
+
+impl GroupMember {
+    pub fn is_guest(&self) -> bool {
+        self.guest_id.is_some()
+    }
 }
 
 /// Each group has an ID and a name.
+/// The admins can perform privileged operations over the group (the creator is the first one).
+/// The group can have an agreed budget, checked when expenses are added.
 #[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
 #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
 pub struct Group {
     pub id: u128,
     pub name: String,
     pub members: Vec<GroupMember>,
+    pub admins: Vec<AccountId>,
     pub next_expense_id: u32,
+    pub next_guest_id: u32,
+    pub next_recurring_expense_id: u32,
+    pub next_debt_assignment_id: u32,
+    pub next_loan_id: u32,
+    pub budget: Option<GroupBudget>,
+    /// The time (in milliseconds) to settle the debts of an expense without its own deadline
+    pub settle_by_term: Option<u64>,
 }
 
 impl Group {
-    pub fn new(id: u128, name: String, members: Vec<GroupMember>) -> Group {
+    pub fn new(id: u128, name: String, members: Vec<GroupMember>, admin: AccountId) -> Group {
         Group {
             id,
             name,
             members,
+            admins: [admin].to_vec(),
             next_expense_id: 1,
+            next_guest_id: 1,
+            next_recurring_expense_id: 1,
+            next_debt_assignment_id: 1,
+            next_loan_id: 1,
+            budget: None,
+            settle_by_term: None,
         }
     }
+
+    pub fn is_admin(&self, address: AccountId) -> bool {
+        self.admins.iter().any(|admin| *admin == address)
+    }
 }
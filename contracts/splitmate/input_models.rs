@@ -1,4 +1,6 @@
-use crate::expense::DistributionType;
+use crate::budget::{BudgetEnforcement, CategoryBudget};
+use crate::expense::{DistributionType, ExpenseItem, ExpenseSharedLine};
+use crate::recurring::RecurrenceUnit;
 use ink::prelude::{
     vec::Vec,
     string::String,
@@ -20,6 +22,41 @@ pub struct ExpenseInput {
     pub amount: u128,
     pub payer_address: AccountId,
     pub distribution: DistributionInput,
+    pub category: Option<String>,
+    pub settle_by: Option<u64>,
+}
+
+/// The amount must be equal to the sum of the items and shared lines.
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct ItemizedExpenseInput {
+    pub group_id: u128,
+    pub amount: u128,
+    pub payer_address: AccountId,
+    pub items: Vec<ExpenseItem>,
+    pub shared_lines: Vec<ExpenseSharedLine>,
+    pub category: Option<String>,
+    pub settle_by: Option<u64>,
+}
+
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct MemberTransferInput {
+    pub group_id: u128,
+    pub from_address: AccountId,
+    pub to_address: AccountId,
+    pub amount: u128,
+    pub settle_by: Option<u64>,
+}
+
+/// Without distribution, the refund is credited like the original expense.
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct RefundInput {
+    pub group_id: u128,
+    pub original_expense_id: u32,
+    pub amount: u128,
+    pub distribution: Option<DistributionInput>,
 }
 
 #[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
@@ -42,3 +79,44 @@ pub struct GroupDebtsToPay {
     pub group_id: u128,
     pub takers: Vec<DistributionByMemberInput>,
 }
+
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct PeriodBudgetInput {
+    pub unit: RecurrenceUnit,
+    pub length: u64,
+    pub limit: u128,
+}
+
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct GroupBudgetInput {
+    pub total: Option<u128>,
+    pub categories: Vec<CategoryBudget>,
+    pub period: Option<PeriodBudgetInput>,
+    pub enforcement: BudgetEnforcement,
+}
+
+/// Spends from the group pot to pay an external account.
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct PotSpendInput {
+    pub group_id: u128,
+    pub recipient: AccountId,
+    pub amount: u128,
+    pub distribution: DistributionInput,
+    pub category: Option<String>,
+}
+
+/// The amount due is split in equal instalments, every period (in milliseconds) from the first due moment.
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct LoanInput {
+    pub group_id: u128,
+    pub lender: AccountId,
+    pub principal: u128,
+    pub interest_bps: u32,
+    pub instalments: u32,
+    pub first_due_at: u64,
+    pub instalment_period: u64,
+}
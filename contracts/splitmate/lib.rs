@@ -1,28 +1,184 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
+pub mod assignment;
+pub mod budget;
+pub mod delegation;
+pub mod dispute;
 pub mod errors;
 pub mod expense;
 pub mod group;
 pub mod input_models;
+pub mod loan;
 pub mod output_models;
+pub mod pot;
+pub mod recurring;
+pub mod settlement;
+pub mod stats;
 pub mod utils;
 
 #[ink::contract]
 mod splitmate {
+    use crate::assignment::{DebtAssignment, DebtAssignmentStatus};
+    use crate::budget::BudgetScope;
+    use crate::delegation::{DelegatedAction, Delegation};
+    use crate::dispute::{DisputeResolution, ExpenseDispute};
     use crate::errors::ContractError;
-    use crate::expense::Expense;
+    use crate::expense::{Expense, ExpenseStatus};
     use crate::group::{Group, GroupMember};
-    use crate::input_models::{ExpenseInput, GroupDebtsToPay};
-    use crate::output_models::{GroupSettledDebts, MemberAccount, SettleUpResult};
+    use crate::input_models::{
+        DistributionByMemberInput, ExpenseInput, GroupBudgetInput, GroupDebtsToPay,
+        ItemizedExpenseInput, LoanInput, MemberTransferInput, PotSpendInput, RefundInput,
+    };
+    use crate::loan::Loan;
+    use crate::output_models::{
+        AddedExpense, ExpenseDetails, GroupAudit, GroupBudgetStatus, LoanDetails, MemberAccount,
+        MemberNetBalance, OverdueDebt, SettleUpResult, SettleUpSimulation,
+    };
+    use crate::pot::GroupPot;
+    use crate::recurring::{RecurrenceUnit, RecurringExpense};
+    use crate::settlement::{Settlement, SettlementKind};
+    use crate::stats::GroupStats;
     use crate::utils::{
-        add_to_member_groups, check_group_membership, get_group_by_id,
-        get_member_group_distributions, get_member_groups, process_expense_debts,
-        update_member_group_debt, BaseResult,
+        add_group_expenses, add_member_expense, add_member_expenses, add_to_group_settlements,
+        add_to_member_delegations, add_to_member_groups, apply_group_payment, audit_group_balances,
+        build_group_budget, build_loan, build_pot_spend_expense, build_refund_expense,
+        check_delegation, check_group_admin, check_group_membership, check_member_group_membership,
+        collapse_itemized_expense, get_group_budget_status, get_group_by_id,
+        get_group_overdue_debts, get_guest_address, get_member_debts_to_pay,
+        get_member_group_distributions, get_member_groups, get_member_net_balances,
+        get_planned_debt, materialize_group_recurring_expenses, migrate_member_group, paginate,
+        process_expense_debts, recover_expense_signer, remove_from_member_delegations,
+        repay_member_loan, replace_member_address, replay_group_balances, replay_group_stats,
+        resolve_expense_dispute, revert_expense_debts, settle_member_debts,
+        simulate_group_debts_payments, validate_debt_to_pay, verify_guest_claim, BaseResult,
     };
     use ink::prelude::{string::String, vec::Vec};
     use ink::storage::Mapping;
     use openbrush::contracts::traits::psp22::PSP22Ref;
 
+    /// Emitted when a member grants (or replaces) a delegation to act on their behalf.
+    #[ink(event)]
+    pub struct DelegationGranted {
+        #[ink(topic)]
+        pub principal: AccountId,
+        #[ink(topic)]
+        pub delegate: AccountId,
+        pub group_ids: Vec<u128>,
+        pub actions: Vec<DelegatedAction>,
+        pub expires_at: Option<u64>,
+    }
+
+    /// Emitted when a member revokes the delegation granted to a delegate.
+    #[ink(event)]
+    pub struct DelegationRevoked {
+        #[ink(topic)]
+        pub principal: AccountId,
+        #[ink(topic)]
+        pub delegate: AccountId,
+    }
+
+    /// Emitted when an occurrence of a recurring expense cannot be added.
+    /// The occurrence is skipped and can be added manually as a regular expense.
+    #[ink(event)]
+    pub struct RecurringExpenseFailed {
+        #[ink(topic)]
+        pub group_id: u128,
+        pub recurring_expense_id: u32,
+        pub occurrence: u64,
+        pub error: ContractError,
+    }
+
+    /// Emitted when the spending of a group budget crosses a usage threshold (percentage).
+    #[ink(event)]
+    pub struct BudgetThresholdReached {
+        #[ink(topic)]
+        pub group_id: u128,
+        pub scope: BudgetScope,
+        pub threshold: u8,
+        pub spent: u128,
+        pub limit: u128,
+    }
+
+    /// Emitted when an expense is added over a flagged group budget.
+    #[ink(event)]
+    pub struct ExpenseOverBudget {
+        #[ink(topic)]
+        pub group_id: u128,
+        pub expense_id: u32,
+        pub scope: BudgetScope,
+        pub spent: u128,
+        pub limit: u128,
+    }
+
+    /// Emitted when a creditor forgives (part of) the debt of a group member.
+    #[ink(event)]
+    pub struct DebtForgiven {
+        #[ink(topic)]
+        pub group_id: u128,
+        #[ink(topic)]
+        pub creditor: AccountId,
+        #[ink(topic)]
+        pub debtor: AccountId,
+        pub amount: u128,
+    }
+
+    /// Emitted when a member disputes a group expense.
+    #[ink(event)]
+    pub struct ExpenseDisputed {
+        #[ink(topic)]
+        pub group_id: u128,
+        pub expense_id: u32,
+        #[ink(topic)]
+        pub disputed_by: AccountId,
+        pub reason_hash: [u8; 32],
+        pub is_frozen: bool,
+    }
+
+    /// Emitted when the dispute of a group expense is resolved.
+    #[ink(event)]
+    pub struct ExpenseDisputeResolved {
+        #[ink(topic)]
+        pub group_id: u128,
+        pub expense_id: u32,
+        pub resolution: DisputeResolution,
+    }
+
+    /// Emitted by the overdue check for every member with overdue debts.
+    #[ink(event)]
+    pub struct DebtOverdue {
+        #[ink(topic)]
+        pub group_id: u128,
+        #[ink(topic)]
+        pub member_account: AccountId,
+        pub overdue_amount: u128,
+        pub overdue_since: u64,
+        pub age: u64,
+    }
+
+    /// Emitted when a borrower repays (part of) a loan.
+    #[ink(event)]
+    pub struct LoanRepaid {
+        #[ink(topic)]
+        pub group_id: u128,
+        pub loan_id: u32,
+        #[ink(topic)]
+        pub borrower: AccountId,
+        #[ink(topic)]
+        pub lender: AccountId,
+        pub amount: u128,
+    }
+
+    /// Emitted when an expense leaves a member debt above the member auto-settle threshold.
+    #[ink(event)]
+    pub struct AutoSettleRequired {
+        #[ink(topic)]
+        pub group_id: u128,
+        #[ink(topic)]
+        pub member_account: AccountId,
+        pub debt_value: i128,
+        pub threshold: u128,
+    }
+
     #[ink(storage)]
     pub struct Splitmate {
         /// ERC20 token address
@@ -31,8 +187,34 @@ mod splitmate {
         pub groups: Mapping<u128, Group>,
         /// Mapping Group ID -> Group expenses
         pub group_expenses: Mapping<u128, Vec<Expense>>,
+        /// Mapping Group ID -> Group settlements
+        pub group_settlements: Mapping<u128, Vec<Settlement>>,
+        /// Mapping Group ID -> Group recurring expenses
+        pub group_recurring_expenses: Mapping<u128, Vec<RecurringExpense>>,
+        /// Mapping Group ID -> Group spending statistics
+        pub group_stats: Mapping<u128, GroupStats>,
+        /// Mapping Group ID -> Group debt assignments
+        pub group_debt_assignments: Mapping<u128, Vec<DebtAssignment>>,
+        /// Mapping Group ID -> Group loans
+        pub group_loans: Mapping<u128, Vec<Loan>>,
+        /// Mapping Group ID -> Group pot
+        pub group_pots: Mapping<u128, GroupPot>,
+        /// Mapping (Group ID, Expense ID) -> Expense dispute
+        pub expense_disputes: Mapping<(u128, u32), ExpenseDispute>,
+        /// Mapping (Group ID, Member) -> Debt above which the member debts are settled automatically
+        pub auto_settle_thresholds: Mapping<(u128, AccountId), u128>,
         /// Mapping Member -> Group IDs
         pub member_groups: Mapping<AccountId, Vec<u128>>,
+        /// Mapping (Group ID, Guest ID) -> Guest invite key account
+        pub guest_invites: Mapping<(u128, u32), AccountId>,
+        /// Mapping (Group ID, Lost member) -> Requested new member addresses
+        pub account_recoveries: Mapping<(u128, AccountId), Vec<AccountId>>,
+        /// Mapping (Principal, Delegate) -> Delegation
+        pub delegations: Mapping<(AccountId, AccountId), Delegation>,
+        /// Mapping Member -> (Principal, Delegate) keys of the member delegations
+        pub member_delegations: Mapping<AccountId, Vec<(AccountId, AccountId)>>,
+        /// Mapping Signer -> Next signed submission nonce
+        pub signer_nonces: Mapping<AccountId, u64>,
         /// Group ID incremental
         pub next_group_id: u128,
     }
@@ -44,12 +226,25 @@ mod splitmate {
                 token_address,
                 groups: Mapping::default(),
                 group_expenses: Mapping::default(),
+                group_settlements: Mapping::default(),
+                group_recurring_expenses: Mapping::default(),
+                group_stats: Mapping::default(),
+                group_debt_assignments: Mapping::default(),
+                group_loans: Mapping::default(),
+                group_pots: Mapping::default(),
+                expense_disputes: Mapping::default(),
+                auto_settle_thresholds: Mapping::default(),
                 member_groups: Mapping::default(),
+                guest_invites: Mapping::default(),
+                account_recoveries: Mapping::default(),
+                delegations: Mapping::default(),
+                member_delegations: Mapping::default(),
+                signer_nonces: Mapping::default(),
                 next_group_id: 1,
             }
         }
 
-        /// Adds a new group with the caller as the first member.
+        /// Adds a new group with the caller as the first member and admin.
         /// Includes a group name and a caller representative name.
         /// Initializes the caller with zero debts.
         /// Adds the group ID to the Mapping Member -> Group IDs.
@@ -62,15 +257,20 @@ mod splitmate {
                 address: caller_address,
                 name: caller_name,
                 debt_value: 0,
+                guest_id: None,
             }]
             .to_vec();
 
-            let new_group = Group::new(next_group_id, group_name, new_group_members);
+            let new_group =
+                Group::new(next_group_id, group_name, new_group_members, caller_address);
             self.groups.insert(next_group_id, &new_group);
 
             add_to_member_groups(self, caller_address, next_group_id);
 
-            self.next_group_id = self.next_group_id.checked_add(1).unwrap();
+            self.next_group_id = self
+                .next_group_id
+                .checked_add(1)
+                .ok_or(ContractError::Overflow)?;
 
             Ok(())
         }
@@ -88,15 +288,273 @@ mod splitmate {
                 address: caller_address,
                 name: caller_name,
                 debt_value: 0,
+                guest_id: None,
+            });
+
+            self.groups.insert(group_id, &group);
+
+            add_to_member_groups(self, caller_address, group_id);
+
+            Ok(())
+        }
+
+        /// Adds a guest member without account to the group.
+        /// Checks if the caller is in the specified group.
+        /// The guest gets a placeholder address to be used in the expense distributions.
+        /// Adds the invite key account to the Mapping (Group ID, Guest ID) -> Guest invite.
+        /// The guest gets the matching ECDSA private key to claim the member later.
+        #[ink(message)]
+        pub fn add_guest(
+            &mut self,
+            group_id: u128,
+            guest_name: String,
+            invite_account: AccountId,
+        ) -> Result<AccountId, ContractError> {
+            let mut group = check_group_membership(&self, group_id)?;
+            let guest_id = group.next_guest_id;
+            let guest_address = get_guest_address(group_id, guest_id);
+
+            group.members.push(GroupMember {
+                address: guest_address,
+                name: guest_name,
+                debt_value: 0,
+                guest_id: Some(guest_id),
             });
+            group.next_guest_id = group
+                .next_guest_id
+                .checked_add(1)
+                .ok_or(ContractError::Overflow)?;
+
+            self.groups.insert(group_id, &group);
+            self.guest_invites
+                .insert((group_id, guest_id), &invite_account);
+
+            Ok(guest_address)
+        }
+
+        /// Claims a guest member of the group with its invite key.
+        /// Checks if the caller is not already in the group.
+        /// Checks if the signature of the caller address was made with the invite key.
+        /// Replaces the guest placeholder address with the caller one in the group and its history.
+        /// Adds the group ID to the Mapping Member -> Group IDs.
+        #[ink(message)]
+        pub fn claim_guest(
+            &mut self,
+            group_id: u128,
+            guest_id: u32,
+            signature: [u8; 65],
+        ) -> BaseResult {
+            let caller_address = self.env().caller();
+            let mut group = get_group_by_id(&self, group_id)?;
+
+            if group.members.iter().any(|m| m.address == caller_address) {
+                return Err(ContractError::MemberIsAlreadyInTheGroup);
+            }
+
+            let invite_account = self
+                .guest_invites
+                .get((group_id, guest_id))
+                .ok_or(ContractError::GuestDoesNotExist)?;
+            verify_guest_claim(
+                &self,
+                group_id,
+                guest_id,
+                caller_address,
+                invite_account,
+                &signature,
+            )?;
+
+            replace_member_address(
+                self,
+                &mut group,
+                get_guest_address(group_id, guest_id),
+                caller_address,
+            )?;
+            let member = group
+                .members
+                .iter_mut()
+                .find(|m| m.address == caller_address)
+                .ok_or(ContractError::MemberNotFound)?;
+            member.guest_id = None;
 
             self.groups.insert(group_id, &group);
+            self.guest_invites.remove((group_id, guest_id));
 
             add_to_member_groups(self, caller_address, group_id);
 
             Ok(())
         }
 
+        /// Migrates the caller account to a new address in all his groups.
+        /// Checks if the new address is not already in any of the groups.
+        /// Replaces the caller address in the group members and history.
+        /// Moves the group IDs in the Mapping Member -> Group IDs.
+        #[ink(message)]
+        pub fn migrate_account(&mut self, new_address: AccountId) -> BaseResult {
+            let caller_address = self.env().caller();
+
+            if new_address == caller_address {
+                return Err(ContractError::MigrationToTheSameAddress);
+            }
+
+            let member_groups = self
+                .member_groups
+                .get(caller_address)
+                .unwrap_or(Vec::<u128>::new());
+
+            for group_id in member_groups {
+                migrate_member_group(self, group_id, caller_address, new_address)?;
+            }
+
+            Ok(())
+        }
+
+        /// Requests the recovery of a lost member account in a group.
+        /// Checks if the caller is not already in the group.
+        /// Checks if the caller has no pending recovery for the lost member.
+        /// Adds the caller to the Mapping (Group ID, Lost member) -> Requested new member addresses.
+        /// The recovery must be approved by a group admin.
+        #[ink(message)]
+        pub fn request_account_recovery(
+            &mut self,
+            group_id: u128,
+            lost_address: AccountId,
+        ) -> BaseResult {
+            let caller_address = self.env().caller();
+            let group = get_group_by_id(&self, group_id)?;
+
+            if group.members.iter().any(|m| m.address == caller_address) {
+                return Err(ContractError::MemberIsAlreadyInTheGroup);
+            }
+
+            let lost_member = group
+                .members
+                .iter()
+                .find(|m| m.address == lost_address)
+                .ok_or(ContractError::MemberNotFound)?;
+            if lost_member.is_guest() {
+                return Err(ContractError::MemberIsAGuest);
+            }
+
+            let mut requested_addresses = self
+                .account_recoveries
+                .get((group_id, lost_address))
+                .unwrap_or(Vec::<AccountId>::new());
+            if requested_addresses.contains(&caller_address) {
+                return Err(ContractError::AccountRecoveryIsAlreadyRequested);
+            }
+            requested_addresses.push(caller_address);
+
+            self.account_recoveries
+                .insert((group_id, lost_address), &requested_addresses);
+
+            Ok(())
+        }
+
+        /// Approves the recovery of a lost member account in a group.
+        /// Checks if the caller is one of the group admins.
+        /// Checks if the new address is one of the requested ones for the lost member.
+        /// Moves the lost member to the new address in the group.
+        /// Drops the other pending recoveries of the lost member.
+        #[ink(message)]
+        pub fn approve_account_recovery(
+            &mut self,
+            group_id: u128,
+            lost_address: AccountId,
+            new_address: AccountId,
+        ) -> BaseResult {
+            check_group_admin(&self, group_id)?;
+
+            let requested_addresses = self
+                .account_recoveries
+                .get((group_id, lost_address))
+                .ok_or(ContractError::AccountRecoveryDoesNotExist)?;
+            if !requested_addresses.contains(&new_address) {
+                return Err(ContractError::AccountRecoveryAddressMismatch);
+            }
+
+            migrate_member_group(self, group_id, lost_address, new_address)
+        }
+
+        /// Rejects a pending recovery of a lost member account in a group.
+        /// Checks if the caller is one of the group admins.
+        #[ink(message)]
+        pub fn reject_account_recovery(
+            &mut self,
+            group_id: u128,
+            lost_address: AccountId,
+            new_address: AccountId,
+        ) -> BaseResult {
+            check_group_admin(&self, group_id)?;
+
+            let mut requested_addresses = self
+                .account_recoveries
+                .get((group_id, lost_address))
+                .ok_or(ContractError::AccountRecoveryDoesNotExist)?;
+            if !requested_addresses.contains(&new_address) {
+                return Err(ContractError::AccountRecoveryDoesNotExist);
+            }
+            requested_addresses.retain(|address| *address != new_address);
+
+            if requested_addresses.len() == 0 {
+                self.account_recoveries.remove((group_id, lost_address));
+            } else {
+                self.account_recoveries
+                    .insert((group_id, lost_address), &requested_addresses);
+            }
+
+            Ok(())
+        }
+
+        /// Makes a group member one of the group admins.
+        /// Checks if the caller is one of the group admins.
+        /// Checks if the member is in the group and is not a guest.
+        #[ink(message)]
+        pub fn add_group_admin(&mut self, group_id: u128, member_address: AccountId) -> BaseResult {
+            let mut group = check_group_admin(&self, group_id)?;
+
+            let member = group
+                .members
+                .iter()
+                .find(|m| m.address == member_address)
+                .ok_or(ContractError::MemberIsNotInTheGroup)?;
+            if member.is_guest() {
+                return Err(ContractError::MemberIsAGuest);
+            }
+            if group.is_admin(member_address) {
+                return Err(ContractError::MemberIsAlreadyAnAdmin);
+            }
+
+            group.admins.push(member_address);
+            self.groups.insert(group_id, &group);
+
+            Ok(())
+        }
+
+        /// Removes a member from the group admins.
+        /// Checks if the caller is one of the group admins.
+        /// Checks if the group keeps at least one admin.
+        #[ink(message)]
+        pub fn remove_group_admin(
+            &mut self,
+            group_id: u128,
+            admin_address: AccountId,
+        ) -> BaseResult {
+            let mut group = check_group_admin(&self, group_id)?;
+
+            if !group.is_admin(admin_address) {
+                return Err(ContractError::MemberIsNotAnAdmin);
+            }
+            if group.admins.len() == 1 {
+                return Err(ContractError::GroupMustHaveAnAdmin);
+            }
+
+            group.admins.retain(|admin| *admin != admin_address);
+            self.groups.insert(group_id, &group);
+
+            Ok(())
+        }
+
         /// Adds an expense to a specific group.
         /// Checks if the caller is in the specified group.
         /// Validates the expense values.
@@ -105,119 +563,1282 @@ mod splitmate {
         /// Updates the Group Expense ID incremental.
         #[ink(message)]
         pub fn add_expense(&mut self, expense_to_add: ExpenseInput) -> BaseResult {
-            let mut group = check_group_membership(&self, expense_to_add.group_id)?;
-            let expense = Expense::new(group.next_expense_id.clone(), expense_to_add);
+            add_member_expense(self, self.env().caller(), expense_to_add)
+        }
 
-            expense.validate()?;
-            process_expense_debts(&mut group, &expense)?;
+        /// Adds an itemized expense (E.g. a receipt) to a specific group.
+        /// Checks if the caller is in the specified group.
+        /// Collapses the items and shared lines into the amount each member must pay.
+        /// Validates the items and shared lines sum the expense amount.
+        /// Keeps the items and shared lines in the expense.
+        #[ink(message)]
+        pub fn add_itemized_expense(
+            &mut self,
+            itemized_expense: ItemizedExpenseInput,
+        ) -> BaseResult {
+            let caller = self.env().caller();
+            let expense_to_add = collapse_itemized_expense(&itemized_expense)?;
+
+            let mut expense = Expense::new(0, caller, expense_to_add);
+            expense.items = itemized_expense.items;
+            expense.shared_lines = itemized_expense.shared_lines;
+            expense.category = itemized_expense.category;
+
+            add_group_expenses(self, caller, [expense].to_vec())?;
+
+            Ok(())
+        }
+
+        /// Adds a direct transfer between two members of a specific group.
+        /// Checks if the caller is in the specified group.
+        /// The sender is owed the amount by the recipient.
+        #[ink(message)]
+        pub fn add_member_transfer(&mut self, transfer_to_add: MemberTransferInput) -> BaseResult {
+            let caller = self.env().caller();
+            check_group_membership(&self, transfer_to_add.group_id)?;
+            if transfer_to_add.from_address == transfer_to_add.to_address {
+                return Err(ContractError::MemberTransferToTheSameMember);
+            }
+
+            let transfer = Expense::new_transfer(0, caller, transfer_to_add);
+            add_group_expenses(self, caller, [transfer].to_vec())?;
+
+            Ok(())
+        }
+
+        /// Adds a refund of an expense to a specific group.
+        /// Checks if the caller is in the specified group.
+        /// Checks the refunds do not exceed the original expense amount.
+        /// The original payers receive the refund and the distribution members are credited.
+        #[ink(message)]
+        pub fn add_refund(&mut self, refund_to_add: RefundInput) -> BaseResult {
+            let caller = self.env().caller();
+            check_group_membership(&self, refund_to_add.group_id)?;
+
+            let refund = build_refund_expense(&self, caller, refund_to_add)?;
+            add_group_expenses(self, caller, [refund].to_vec())?;
+
+            Ok(())
+        }
+
+        /// Disputes an expense of a specific group.
+        /// Checks if the caller is in the specified group and the expense is active.
+        /// If frozen, the expense does not affect the group balances until the dispute is resolved.
+        /// Adds the dispute to the Mapping (Group ID, Expense ID) -> Expense dispute.
+        #[ink(message)]
+        pub fn dispute_expense(
+            &mut self,
+            group_id: u128,
+            expense_id: u32,
+            reason_hash: [u8; 32],
+            freeze: bool,
+        ) -> BaseResult {
+            let caller = self.env().caller();
+            let mut group = check_group_membership(&self, group_id)?;
 
             let mut group_expenses = self
                 .group_expenses
-                .get(expense.group_id)
+                .get(group_id)
                 .unwrap_or(Vec::<Expense>::new());
-            group_expenses.push(expense.clone());
-            self.group_expenses
-                .insert(expense.group_id, &group_expenses);
+            let expense = group_expenses
+                .iter_mut()
+                .find(|e| e.id == expense_id)
+                .ok_or(ContractError::ExpenseDoesNotExist)?;
+            if expense.status != ExpenseStatus::ACTIVE {
+                return Err(ContractError::ExpenseIsNotActive);
+            }
+
+            if freeze {
+                revert_expense_debts(&mut group, expense)?;
+                expense.status = ExpenseStatus::FROZEN;
+                self.groups.insert(group_id, &group);
+            } else {
+                expense.status = ExpenseStatus::DISPUTED;
+            }
+            self.group_expenses.insert(group_id, &group_expenses);
 
-            group.next_expense_id = group.next_expense_id.checked_add(1).unwrap();
-            self.groups.insert(expense.group_id, &group);
+            self.expense_disputes.insert(
+                (group_id, expense_id),
+                &ExpenseDispute {
+                    group_id,
+                    expense_id,
+                    disputed_by: caller,
+                    reason_hash,
+                    votes: Vec::new(),
+                },
+            );
+
+            self.env().emit_event(ExpenseDisputed {
+                group_id,
+                expense_id,
+                disputed_by: caller,
+                reason_hash,
+                is_frozen: freeze,
+            });
 
             Ok(())
         }
 
-        /// Settles up selected debts for specific groups.
-        /// Transfers ERC20 tokens for each debt.  
-        /// Updates the group debts.
-        /// Informs if all the debts were paid.     
+        /// Votes a resolution for the dispute of a group expense, replacing the previous vote.
+        /// Checks if the caller is in the specified group.
+        /// Resolves the dispute when the majority of the group members (guests excluded) vote the same resolution.
         #[ink(message)]
-        pub fn settle_up(
+        pub fn vote_expense_dispute(
             &mut self,
-            debts_to_pay: Vec<GroupDebtsToPay>,
-        ) -> Result<SettleUpResult, ContractError> {
-            // ToDo: Add debts validation
-            let mut total_settled_debts = Vec::<GroupSettledDebts>::new();
+            group_id: u128,
+            expense_id: u32,
+            resolution: DisputeResolution,
+        ) -> BaseResult {
+            let caller = self.env().caller();
+            let group = check_group_membership(&self, group_id)?;
 
-            for group_debts_to_pay in debts_to_pay {
-                let mut group = check_group_membership(&self, group_debts_to_pay.group_id)?;
+            let mut expense_dispute = self
+                .expense_disputes
+                .get((group_id, expense_id))
+                .ok_or(ContractError::ExpenseDisputeDoesNotExist)?;
+            expense_dispute.add_vote(caller, resolution.clone());
 
-                let mut group_settled_debt_amount: u128 = 0;
-                let mut group_settled_debts = GroupSettledDebts {
-                    group_id: group.id,
-                    takers: Vec::<AccountId>::new(),
-                };
+            let voters = group.members.iter().filter(|m| !m.is_guest()).count() as u32;
+            if expense_dispute.count_votes(&resolution) * 2 <= voters {
+                self.expense_disputes
+                    .insert((group_id, expense_id), &expense_dispute);
+                return Ok(());
+            }
 
-                for taker in group_debts_to_pay.takers {
-                    // ToDo: Check account balances before transfers
-                    if PSP22Ref::transfer(
-                        &mut self.token_address,
-                        taker.member_address,
-                        taker.value,
-                        Vec::new(),
-                    )
-                    .is_err()
-                    {
-                        update_member_group_debt(
-                            &mut group,
-                            self.env().caller(),
-                            false,
-                            group_settled_debt_amount,
-                        );
-
-                        total_settled_debts.push(group_settled_debts);
-
-                        return Ok(SettleUpResult {
-                            result: false,
-                            total_settled_debts: Some(total_settled_debts),
-                        });
-                    };
-
-                    update_member_group_debt(&mut group, taker.member_address, true, taker.value);
-
-                    group_settled_debt_amount =
-                        group_settled_debt_amount.checked_add(taker.value).unwrap();
-
-                    group_settled_debts.takers.push(taker.member_address);
-                }
+            resolve_expense_dispute(self, group_id, expense_id, resolution.clone())?;
+            self.env().emit_event(ExpenseDisputeResolved {
+                group_id,
+                expense_id,
+                resolution,
+            });
 
-                update_member_group_debt(
-                    &mut group,
-                    self.env().caller(),
-                    false,
-                    group_settled_debt_amount,
-                );
-                total_settled_debts.push(group_settled_debts);
+            Ok(())
+        }
+
+        /// Resolves the dispute of a group expense.
+        /// Checks if the caller is one of the group admins.
+        #[ink(message)]
+        pub fn resolve_expense_dispute(
+            &mut self,
+            group_id: u128,
+            expense_id: u32,
+            resolution: DisputeResolution,
+        ) -> BaseResult {
+            check_group_admin(&self, group_id)?;
+            if !self.expense_disputes.contains((group_id, expense_id)) {
+                return Err(ContractError::ExpenseDisputeDoesNotExist);
             }
 
-            Ok(SettleUpResult {
-                result: true,
-                total_settled_debts: None,
-            })
+            resolve_expense_dispute(self, group_id, expense_id, resolution.clone())?;
+            self.env().emit_event(ExpenseDisputeResolved {
+                group_id,
+                expense_id,
+                resolution,
+            });
+
+            Ok(())
         }
 
-        /// Gets the specified member groups and debts.
+        /// Gets the dispute of a group expense.
         #[ink(message)]
-        pub fn get_member_account(&self) -> Result<MemberAccount, ContractError> {
+        pub fn get_expense_dispute(
+            &self,
+            group_id: u128,
+            expense_id: u32,
+        ) -> Result<ExpenseDispute, ContractError> {
+            check_group_membership(&self, group_id)?;
+            self.expense_disputes
+                .get((group_id, expense_id))
+                .ok_or(ContractError::ExpenseDisputeDoesNotExist)
+        }
+
+        /// Adds several expenses to their groups in a single call.
+        /// Checks if the caller is in every specified group.
+        /// Validates all the expenses before storing any of them.
+        /// Informs the ID assigned to each expense.
+        #[ink(message)]
+        pub fn add_expenses(
+            &mut self,
+            expenses_to_add: Vec<ExpenseInput>,
+        ) -> Result<Vec<AddedExpense>, ContractError> {
+            add_member_expenses(self, self.env().caller(), expenses_to_add)
+        }
+
+        /// Adds a recurring expense to a specific group.
+        /// Checks if the caller is in the specified group.
+        /// Validates the expense values against the group.
+        /// Checks the template has no settle-by date (each occurrence gets the group settle-by term).
+        /// The first expense is created at the start moment, then every period until the end.
+        /// Adds the recurring expense to the Mapping Group ID -> Group recurring expenses.
+        #[ink(message)]
+        pub fn add_recurring_expense(
+            &mut self,
+            expense_to_add: ExpenseInput,
+            unit: RecurrenceUnit,
+            period: u64,
+            starts_at: u64,
+            ends_at: Option<u64>,
+        ) -> Result<u32, ContractError> {
             let caller = self.env().caller();
-            let groups = get_member_groups(&self, caller)?;
-            let debts_by_group = get_member_group_distributions(&self, caller)?;
+            let mut group = check_group_membership(&self, expense_to_add.group_id)?;
 
-            Ok(MemberAccount {
-                groups,
-                debts_by_group,
-            })
+            if period == 0 {
+                return Err(ContractError::RecurringPeriodIsZero);
+            }
+            if expense_to_add.settle_by.is_some() {
+                return Err(ContractError::RecurringExpenseWithSettleBy);
+            }
+
+            let expense = Expense::new(0, caller, expense_to_add.clone());
+            expense.validate()?;
+            process_expense_debts(&mut group.clone(), &expense)?;
+
+            let recurring_expense = RecurringExpense {
+                id: group.next_recurring_expense_id,
+                group_id: group.id,
+                created_by: caller,
+                expense: expense_to_add,
+                unit,
+                period,
+                next_occurrence: starts_at,
+                ends_at,
+            };
+
+            let mut group_recurring_expenses = self
+                .group_recurring_expenses
+                .get(group.id)
+                .unwrap_or(Vec::<RecurringExpense>::new());
+            group_recurring_expenses.push(recurring_expense.clone());
+            self.group_recurring_expenses
+                .insert(group.id, &group_recurring_expenses);
+
+            group.next_recurring_expense_id = group
+                .next_recurring_expense_id
+                .checked_add(1)
+                .ok_or(ContractError::Overflow)?;
+            self.groups.insert(group.id, &group);
+
+            Ok(recurring_expense.id)
         }
 
-        /// Gets the specified group data.
+        /// Removes a recurring expense from a specific group.
+        /// Checks if the caller is the recurring expense creator or a group admin.
         #[ink(message)]
-        pub fn get_group(&self, group_id: u128) -> Result<Group, ContractError> {
-            check_group_membership(&self, group_id)
+        pub fn remove_recurring_expense(
+            &mut self,
+            group_id: u128,
+            recurring_expense_id: u32,
+        ) -> BaseResult {
+            let caller = self.env().caller();
+            let group = check_group_membership(&self, group_id)?;
+
+            let mut group_recurring_expenses = self
+                .group_recurring_expenses
+                .get(group_id)
+                .unwrap_or(Vec::<RecurringExpense>::new());
+            let recurring_expense_index = group_recurring_expenses
+                .iter()
+                .position(|r| r.id == recurring_expense_id)
+                .ok_or(ContractError::RecurringExpenseDoesNotExist)?;
+
+            if group_recurring_expenses[recurring_expense_index].created_by != caller
+                && !group.is_admin(caller)
+            {
+                return Err(ContractError::CallerIsNotTheCreator);
+            }
+
+            group_recurring_expenses.remove(recurring_expense_index);
+            self.group_recurring_expenses
+                .insert(group_id, &group_recurring_expenses);
+
+            Ok(())
         }
 
-        /// Gets all the expenses of the specified group.
+        /// Creates the due expenses of the group recurring expenses.
+        /// Can be called by anyone.
+        /// Skips the occurrences that fail, informing them with an event.
+        /// Informs the group and ID of each created expense.
         #[ink(message)]
-        pub fn get_expenses_by_group(&self, group_id: u128) -> Result<Vec<Expense>, ContractError> {
+        pub fn materialize_recurring(
+            &mut self,
+            group_id: u128,
+        ) -> Result<Vec<AddedExpense>, ContractError> {
+            materialize_group_recurring_expenses(self, group_id)
+        }
+
+        /// Gets all the recurring expenses of the specified group.
+        #[ink(message)]
+        pub fn get_recurring_expenses_by_group(
+            &self,
+            group_id: u128,
+        ) -> Result<Vec<RecurringExpense>, ContractError> {
+            check_group_membership(&self, group_id)?;
+            Ok(self
+                .group_recurring_expenses
+                .get(group_id)
+                .unwrap_or(Vec::<RecurringExpense>::new()))
+        }
+
+        /// Adds an expense to a specific group on behalf of a principal member.
+        /// Checks if the caller is a valid delegate of the principal for the group.
+        #[ink(message)]
+        pub fn add_expense_on_behalf(
+            &mut self,
+            principal: AccountId,
+            expense_to_add: ExpenseInput,
+        ) -> BaseResult {
+            check_delegation(
+                &self,
+                principal,
+                expense_to_add.group_id,
+                DelegatedAction::AddExpense,
+            )?;
+            add_member_expense(self, principal, expense_to_add)
+        }
+
+        /// Adds an expense signed off-chain by a member, submitted by any account (relayer).
+        /// Recovers the signer from the ECDSA signature and records it as the expense creator.
+        /// Checks the nonce is the next one of the signer to prevent replays.
+        /// Checks if the signer is in the specified group.
+        #[ink(message)]
+        pub fn add_expense_signed(
+            &mut self,
+            expense_to_add: ExpenseInput,
+            nonce: u64,
+            signature: [u8; 65],
+        ) -> BaseResult {
+            let signer = recover_expense_signer(&self, &expense_to_add, nonce, &signature)?;
+
+            if nonce != self.signer_nonces.get(signer).unwrap_or(0) {
+                return Err(ContractError::InvalidNonce);
+            }
+            let next_nonce = nonce.checked_add(1).ok_or(ContractError::Overflow)?;
+            self.signer_nonces.insert(signer, &next_nonce);
+
+            add_member_expense(self, signer, expense_to_add)
+        }
+
+        /// Gets the nonce expected in the next signed submission of the signer.
+        #[ink(message)]
+        pub fn get_signer_nonce(&self, signer: AccountId) -> u64 {
+            self.signer_nonces.get(signer).unwrap_or(0)
+        }
+
+        /// Settles up selected debts for specific groups.
+        /// Validates each debt against the current group balances.
+        /// Transfers ERC20 tokens from the caller for each debt.
+        /// Stops transferring after the first failed payment, skipping the remaining ones.
+        /// Updates the group debts.
+        /// Adds each paid debt to the Mapping Group ID -> Group settlements.
+        /// Informs the status of every requested payment.
+        #[ink(message)]
+        pub fn settle_up(
+            &mut self,
+            debts_to_pay: Vec<GroupDebtsToPay>,
+        ) -> Result<SettleUpResult, ContractError> {
+            settle_member_debts(self, self.env().caller(), debts_to_pay)
+        }
+
+        /// Sets the debt above which the caller debts in a group are settled automatically.
+        /// The caller must give an allowance to the contract for the automatic settlements.
+        /// Removes the threshold if not specified.
+        #[ink(message)]
+        pub fn set_auto_settle_threshold(
+            &mut self,
+            group_id: u128,
+            threshold: Option<u128>,
+        ) -> BaseResult {
+            let caller = self.env().caller();
             check_group_membership(&self, group_id)?;
-            Ok(self.group_expenses.get(group_id).unwrap())
+
+            match threshold {
+                Some(threshold) => {
+                    self.auto_settle_thresholds
+                        .insert((group_id, caller), &threshold);
+                }
+                None => self.auto_settle_thresholds.remove((group_id, caller)),
+            }
+
+            Ok(())
+        }
+
+        /// Gets the auto-settle threshold of a member in a group (if any).
+        #[ink(message)]
+        pub fn get_auto_settle_threshold(
+            &self,
+            group_id: u128,
+            member_address: AccountId,
+        ) -> Option<u128> {
+            self.auto_settle_thresholds.get((group_id, member_address))
+        }
+
+        /// Settles up the member debts in a group according to the group debts distribution.
+        /// Can be called by any account, once the member debt is above the member auto-settle threshold.
+        /// Transfers ERC20 tokens from the member for each debt.
+        #[ink(message)]
+        pub fn auto_settle(
+            &mut self,
+            group_id: u128,
+            member_address: AccountId,
+        ) -> Result<SettleUpResult, ContractError> {
+            let threshold = self
+                .auto_settle_thresholds
+                .get((group_id, member_address))
+                .ok_or(ContractError::AutoSettleIsNotEnabled)?;
+            let group = check_member_group_membership(&self, member_address, group_id)?;
+
+            let member = group
+                .members
+                .iter()
+                .find(|m| m.address == member_address)
+                .ok_or(ContractError::MemberNotFound)?;
+            if member.debt_value <= 0 || member.debt_value.unsigned_abs() <= threshold {
+                return Err(ContractError::DebtIsBelowAutoSettleThreshold);
+            }
+
+            let debts_to_pay = get_member_debts_to_pay(&self, &group, member_address)?;
+            settle_member_debts(self, member_address, [debts_to_pay].to_vec())
+        }
+
+        /// Settles up selected debts for specific groups on behalf of a principal member.
+        /// Checks if the caller is a valid delegate of the principal for every group.
+        /// Transfers ERC20 tokens from the principal for each debt.
+        #[ink(message)]
+        pub fn settle_up_on_behalf(
+            &mut self,
+            principal: AccountId,
+            debts_to_pay: Vec<GroupDebtsToPay>,
+        ) -> Result<SettleUpResult, ContractError> {
+            for group_debts_to_pay in debts_to_pay.iter() {
+                check_delegation(
+                    &self,
+                    principal,
+                    group_debts_to_pay.group_id,
+                    DelegatedAction::SettleUp,
+                )?;
+            }
+            settle_member_debts(self, principal, debts_to_pay)
+        }
+
+        /// Grants a delegate the permission to act on behalf of the caller.
+        /// Checks if the caller is in every specified group.
+        /// Replaces any previous delegation to the same delegate.
+        /// Adds the delegation to the Mapping (Principal, Delegate) -> Delegation.
+        #[ink(message)]
+        pub fn grant_delegation(
+            &mut self,
+            delegate: AccountId,
+            group_ids: Vec<u128>,
+            actions: Vec<DelegatedAction>,
+            expires_at: Option<u64>,
+        ) -> BaseResult {
+            let caller = self.env().caller();
+
+            if delegate == caller {
+                return Err(ContractError::DelegateIsThePrincipal);
+            }
+
+            for group_id in group_ids.iter() {
+                check_group_membership(&self, *group_id)?;
+            }
+
+            let delegation = Delegation {
+                principal: caller,
+                delegate,
+                group_ids,
+                actions,
+                expires_at,
+            };
+            self.delegations.insert((caller, delegate), &delegation);
+            add_to_member_delegations(self, caller, delegate);
+
+            self.env().emit_event(DelegationGranted {
+                principal: caller,
+                delegate,
+                group_ids: delegation.group_ids,
+                actions: delegation.actions,
+                expires_at,
+            });
+
+            Ok(())
+        }
+
+        /// Revokes the delegation granted by the caller to the delegate.
+        #[ink(message)]
+        pub fn revoke_delegation(&mut self, delegate: AccountId) -> BaseResult {
+            let caller = self.env().caller();
+
+            if !self.delegations.contains((caller, delegate)) {
+                return Err(ContractError::DelegationDoesNotExist);
+            }
+            self.delegations.remove((caller, delegate));
+            remove_from_member_delegations(self, caller, delegate);
+
+            self.env().emit_event(DelegationRevoked {
+                principal: caller,
+                delegate,
+            });
+
+            Ok(())
+        }
+
+        /// Gets the delegation granted by the principal to the delegate.
+        #[ink(message)]
+        pub fn get_delegation(
+            &self,
+            principal: AccountId,
+            delegate: AccountId,
+        ) -> Option<Delegation> {
+            self.delegations.get((principal, delegate))
+        }
+
+        /// Simulates the settle up of selected debts for specific groups.
+        /// Validates each debt against the current group balances.
+        /// Checks the caller ERC20 balance and allowance.
+        /// Informs the status of every requested payment and the projected group balances.
+        #[ink(message)]
+        pub fn simulate_settle_up(
+            &self,
+            debts_to_pay: Vec<GroupDebtsToPay>,
+        ) -> Result<SettleUpSimulation, ContractError> {
+            simulate_group_debts_payments(&self, self.env().caller(), debts_to_pay)
+        }
+
+        /// Settles up the caller debts with a member across all their shared groups.
+        /// Nets the pairwise balances of every shared group.
+        /// Transfers ERC20 tokens once for the net debt.
+        /// Updates the debts of both members in every affected group.
+        /// Records the transferred net debt as payments and the offset debts as nettings.
+        #[ink(message)]
+        pub fn settle_across_groups(&mut self, member_address: AccountId) -> BaseResult {
+            let caller = self.env().caller();
+            let net_balance = get_member_net_balances(&self, caller)?
+                .into_iter()
+                .find(|b| b.member_account == member_address)
+                .ok_or(ContractError::NothingToSettle)?;
+
+            if net_balance.net_value < 0 {
+                return Err(ContractError::MemberIsNotTheNetDebtor);
+            }
+
+            let mut groups = Vec::<Group>::new();
+            for group_balance in net_balance.balances_by_group.iter() {
+                let group = get_group_by_id(&self, group_balance.group_id)?;
+                if group
+                    .members
+                    .iter()
+                    .any(|m| m.address == member_address && m.is_guest())
+                {
+                    return Err(ContractError::MemberIsAGuest);
+                }
+                groups.push(group);
+            }
+
+            if net_balance.net_value > 0 {
+                PSP22Ref::transfer_from(
+                    &mut self.token_address,
+                    caller,
+                    member_address,
+                    net_balance.net_value.unsigned_abs(),
+                    Vec::new(),
+                )
+                .map_err(ContractError::from)?;
+            }
+
+            let mut transferred_left = net_balance.net_value.unsigned_abs();
+            for (group_balance, mut group) in net_balance.balances_by_group.into_iter().zip(groups)
+            {
+                let amount = group_balance.value.unsigned_abs();
+                let (giver, taker, paid_amount) = if group_balance.value > 0 {
+                    let paid_amount = amount.min(transferred_left);
+                    transferred_left -= paid_amount;
+                    (caller, member_address, paid_amount)
+                } else {
+                    (member_address, caller, 0)
+                };
+
+                apply_group_payment(&mut group, giver, taker, amount)?;
+
+                self.groups.insert(group.id, &group);
+                if paid_amount > 0 {
+                    add_to_group_settlements(
+                        self,
+                        group.id,
+                        giver,
+                        taker,
+                        paid_amount,
+                        SettlementKind::PAYMENT,
+                    );
+                }
+                if amount > paid_amount {
+                    add_to_group_settlements(
+                        self,
+                        group.id,
+                        giver,
+                        taker,
+                        amount - paid_amount,
+                        SettlementKind::NETTING,
+                    );
+                }
+            }
+
+            Ok(())
+        }
+
+        /// Forgives (part of) the debt of a group member, without any token transfer.
+        /// Checks the caller is owed at least the amount and the debtor owes at least the amount.
+        /// Updates the group debts.
+        /// Adds the forgiveness to the Mapping Group ID -> Group settlements.
+        #[ink(message)]
+        pub fn forgive_debt(
+            &mut self,
+            group_id: u128,
+            debtor: AccountId,
+            amount: u128,
+        ) -> BaseResult {
+            let caller = self.env().caller();
+            let mut group = check_group_membership(&self, group_id)?;
+
+            validate_debt_to_pay(
+                &group,
+                debtor,
+                &DistributionByMemberInput {
+                    member_address: caller,
+                    value: amount,
+                },
+            )?;
+            apply_group_payment(&mut group, debtor, caller, amount)?;
+
+            self.groups.insert(group_id, &group);
+            add_to_group_settlements(
+                self,
+                group_id,
+                debtor,
+                caller,
+                amount,
+                SettlementKind::FORGIVENESS,
+            );
+
+            self.env().emit_event(DebtForgiven {
+                group_id,
+                creditor: caller,
+                debtor,
+                amount,
+            });
+
+            Ok(())
+        }
+
+        /// Proposes to redirect (part of) the debt of a member to a new creditor.
+        /// Checks the debtor owes at least the amount and the new creditor is owed at least the amount.
+        /// Checks the debtor has to pay the caller at least the amount (plus the pending assignments) in the settlement plan.
+        /// The assignment is used by the settlement plan once the new creditor accepts it.
+        /// Adds the assignment to the Mapping Group ID -> Group debt assignments.
+        #[ink(message)]
+        pub fn assign_debt(
+            &mut self,
+            group_id: u128,
+            debtor: AccountId,
+            new_creditor: AccountId,
+            amount: u128,
+        ) -> Result<u32, ContractError> {
+            let caller = self.env().caller();
+            let mut group = check_group_membership(&self, group_id)?;
+
+            if caller == debtor || caller == new_creditor {
+                return Err(ContractError::DebtAssignmentInvolvesTheAssigner);
+            }
+            validate_debt_to_pay(
+                &group,
+                debtor,
+                &DistributionByMemberInput {
+                    member_address: new_creditor,
+                    value: amount,
+                },
+            )?;
+
+            let mut group_debt_assignments = self
+                .group_debt_assignments
+                .get(group_id)
+                .unwrap_or(Vec::<DebtAssignment>::new());
+
+            let mut assigned_amount = amount;
+            for proposed_assignment in group_debt_assignments
+                .iter()
+                .filter(|a| a.assigned_by == caller && a.debtor == debtor && !a.is_accepted())
+            {
+                assigned_amount = assigned_amount
+                    .checked_add(proposed_assignment.amount)
+                    .ok_or(ContractError::Overflow)?;
+            }
+            if get_planned_debt(&self, &group, debtor, caller)? < assigned_amount {
+                return Err(ContractError::AssignerIsNotOwedByTheDebtor);
+            }
+
+            let debt_assignment = DebtAssignment {
+                id: group.next_debt_assignment_id,
+                group_id,
+                assigned_by: caller,
+                debtor,
+                new_creditor,
+                amount,
+                status: DebtAssignmentStatus::Proposed,
+            };
+
+            group_debt_assignments.push(debt_assignment.clone());
+            self.group_debt_assignments
+                .insert(group_id, &group_debt_assignments);
+
+            group.next_debt_assignment_id = group
+                .next_debt_assignment_id
+                .checked_add(1)
+                .ok_or(ContractError::Overflow)?;
+            self.groups.insert(group_id, &group);
+
+            Ok(debt_assignment.id)
+        }
+
+        /// Accepts a debt assignment.
+        /// Checks if the caller is the new creditor of the assignment.
+        /// Checks the assignment is still valid against the current group balances.
+        #[ink(message)]
+        pub fn accept_debt_assignment(
+            &mut self,
+            group_id: u128,
+            debt_assignment_id: u32,
+        ) -> BaseResult {
+            let caller = self.env().caller();
+            let group = check_group_membership(&self, group_id)?;
+
+            let mut group_debt_assignments = self
+                .group_debt_assignments
+                .get(group_id)
+                .unwrap_or(Vec::<DebtAssignment>::new());
+            let debt_assignment = group_debt_assignments
+                .iter_mut()
+                .find(|a| a.id == debt_assignment_id)
+                .ok_or(ContractError::DebtAssignmentDoesNotExist)?;
+
+            if debt_assignment.new_creditor != caller {
+                return Err(ContractError::CallerIsNotTheNewCreditor);
+            }
+            if debt_assignment.is_accepted() {
+                return Err(ContractError::DebtAssignmentIsAlreadyAccepted);
+            }
+            validate_debt_to_pay(
+                &group,
+                debt_assignment.debtor,
+                &DistributionByMemberInput {
+                    member_address: debt_assignment.new_creditor,
+                    value: debt_assignment.amount,
+                },
+            )?;
+
+            debt_assignment.status = DebtAssignmentStatus::Accepted;
+            self.group_debt_assignments
+                .insert(group_id, &group_debt_assignments);
+
+            Ok(())
+        }
+
+        /// Removes a debt assignment.
+        /// Checks if the caller is the new creditor or the member who assigned the debt.
+        #[ink(message)]
+        pub fn reject_debt_assignment(
+            &mut self,
+            group_id: u128,
+            debt_assignment_id: u32,
+        ) -> BaseResult {
+            let caller = self.env().caller();
+            check_group_membership(&self, group_id)?;
+
+            let mut group_debt_assignments = self
+                .group_debt_assignments
+                .get(group_id)
+                .unwrap_or(Vec::<DebtAssignment>::new());
+            let debt_assignment_index = group_debt_assignments
+                .iter()
+                .position(|a| a.id == debt_assignment_id)
+                .ok_or(ContractError::DebtAssignmentDoesNotExist)?;
+
+            let debt_assignment = &group_debt_assignments[debt_assignment_index];
+            if debt_assignment.new_creditor != caller && debt_assignment.assigned_by != caller {
+                return Err(ContractError::CallerIsNotTheNewCreditorOrTheAssigner);
+            }
+
+            group_debt_assignments.remove(debt_assignment_index);
+            self.group_debt_assignments
+                .insert(group_id, &group_debt_assignments);
+
+            Ok(())
+        }
+
+        /// Gets the proposed and accepted debt assignments of the specified group.
+        #[ink(message)]
+        pub fn get_debt_assignments(
+            &self,
+            group_id: u128,
+        ) -> Result<Vec<DebtAssignment>, ContractError> {
+            check_group_membership(&self, group_id)?;
+            Ok(self
+                .group_debt_assignments
+                .get(group_id)
+                .unwrap_or(Vec::<DebtAssignment>::new()))
+        }
+
+        /// Adds a loan from the lender to the caller (borrower), kept apart from the group balances.
+        /// Checks if the caller and the lender are in the specified group.
+        /// Adds the loan to the Mapping Group ID -> Group loans.
+        #[ink(message)]
+        pub fn add_loan(&mut self, loan_to_add: LoanInput) -> Result<u32, ContractError> {
+            let caller = self.env().caller();
+            let mut group = check_group_membership(&self, loan_to_add.group_id)?;
+
+            if loan_to_add.lender == caller {
+                return Err(ContractError::LoanLenderIsTheBorrower);
+            }
+            let lender = group
+                .members
+                .iter()
+                .find(|m| m.address == loan_to_add.lender)
+                .ok_or(ContractError::MemberIsNotInTheGroup)?;
+            if lender.is_guest() {
+                return Err(ContractError::MemberIsAGuest);
+            }
+
+            let loan = build_loan(&self, group.next_loan_id, caller, loan_to_add)?;
+
+            let mut group_loans = self.group_loans.get(group.id).unwrap_or(Vec::<Loan>::new());
+            group_loans.push(loan.clone());
+            self.group_loans.insert(group.id, &group_loans);
+
+            group.next_loan_id = group
+                .next_loan_id
+                .checked_add(1)
+                .ok_or(ContractError::Overflow)?;
+            self.groups.insert(group.id, &group);
+
+            Ok(loan.id)
+        }
+
+        /// Repays (part of) a loan, paying its instalments in order.
+        /// Checks if the caller is the loan borrower.
+        /// Transfers ERC20 tokens from the borrower to the lender.
+        /// Adds the repayment to the Mapping Group ID -> Group settlements.
+        /// Informs the status of the payment.
+        #[ink(message)]
+        pub fn repay_loan(
+            &mut self,
+            group_id: u128,
+            loan_id: u32,
+            amount: u128,
+        ) -> Result<SettleUpResult, ContractError> {
+            repay_member_loan(self, self.env().caller(), group_id, loan_id, amount)
+        }
+
+        /// Repays (part of) a loan on behalf of its borrower.
+        /// Checks if the caller is a valid delegate of the borrower to settle up in the group.
+        /// Transfers ERC20 tokens from the borrower to the lender.
+        #[ink(message)]
+        pub fn repay_loan_on_behalf(
+            &mut self,
+            borrower: AccountId,
+            group_id: u128,
+            loan_id: u32,
+            amount: u128,
+        ) -> Result<SettleUpResult, ContractError> {
+            check_delegation(&self, borrower, group_id, DelegatedAction::SettleUp)?;
+            repay_member_loan(self, borrower, group_id, loan_id, amount)
+        }
+
+        /// Gets a loan of the specified group, with its outstanding amounts and next due instalment.
+        #[ink(message)]
+        pub fn get_loan(&self, group_id: u128, loan_id: u32) -> Result<LoanDetails, ContractError> {
+            check_group_membership(&self, group_id)?;
+
+            let loan = self
+                .group_loans
+                .get(group_id)
+                .unwrap_or(Vec::<Loan>::new())
+                .into_iter()
+                .find(|l| l.id == loan_id)
+                .ok_or(ContractError::LoanDoesNotExist)?;
+
+            Ok(LoanDetails {
+                outstanding: loan.outstanding(),
+                outstanding_principal: loan.outstanding_principal()?,
+                next_instalment: loan.next_instalment(),
+                loan,
+            })
+        }
+
+        /// Gets all the loans of the specified group.
+        #[ink(message)]
+        pub fn get_loans_by_group(&self, group_id: u128) -> Result<Vec<Loan>, ContractError> {
+            check_group_membership(&self, group_id)?;
+            Ok(self.group_loans.get(group_id).unwrap_or(Vec::<Loan>::new()))
+        }
+
+        /// Replaces the stored group balances and stats with the ones recomputed from its history.
+        /// Checks if the caller is one of the group admins.
+        #[ink(message)]
+        pub fn repair_group_balances(
+            &mut self,
+            group_id: u128,
+        ) -> Result<GroupAudit, ContractError> {
+            let group = check_group_admin(&self, group_id)?;
+            let audit = audit_group_balances(&self, &group)?;
+
+            if !audit.is_consistent {
+                let replayed_group = replay_group_balances(&self, &group)?;
+                self.groups.insert(group_id, &replayed_group);
+            }
+            let replayed_stats = replay_group_stats(&self, &group)?;
+            self.group_stats.insert(group_id, &replayed_stats);
+
+            Ok(audit)
+        }
+
+        /// Recomputes the group balances replaying all its expenses and settlements.
+        /// Reports the members whose stored balance differs from the recomputed one.
+        #[ink(message)]
+        pub fn audit_group(&self, group_id: u128) -> Result<GroupAudit, ContractError> {
+            let group = check_group_membership(&self, group_id)?;
+            audit_group_balances(&self, &group)
+        }
+
+        /// Gets the caller balances with each member netted across all his groups.
+        #[ink(message)]
+        pub fn get_net_balances(&self) -> Result<Vec<MemberNetBalance>, ContractError> {
+            get_member_net_balances(&self, self.env().caller())
+        }
+
+        /// Gets the specified member groups and debts.
+        #[ink(message)]
+        pub fn get_member_account(&self) -> Result<MemberAccount, ContractError> {
+            let caller = self.env().caller();
+            let groups = get_member_groups(&self, caller)?;
+            let debts_by_group = get_member_group_distributions(&self, caller)?;
+
+            Ok(MemberAccount {
+                groups,
+                debts_by_group,
+            })
+        }
+
+        /// Gets the specified group data.
+        #[ink(message)]
+        pub fn get_group(&self, group_id: u128) -> Result<Group, ContractError> {
+            check_group_membership(&self, group_id)
+        }
+
+        /// Gets a page of the specified group settlements, from the oldest to the newest.
+        #[ink(message)]
+        pub fn get_settlements_by_group(
+            &self,
+            group_id: u128,
+            offset: u32,
+            limit: u32,
+        ) -> Result<Vec<Settlement>, ContractError> {
+            check_group_membership(&self, group_id)?;
+            let group_settlements = self
+                .group_settlements
+                .get(group_id)
+                .unwrap_or(Vec::<Settlement>::new());
+
+            Ok(paginate(group_settlements, offset, limit))
+        }
+
+        /// Gets a page of the settlements paid or received by the caller in all his groups.
+        #[ink(message)]
+        pub fn get_member_settlements(
+            &self,
+            offset: u32,
+            limit: u32,
+        ) -> Result<Vec<Settlement>, ContractError> {
+            let caller = self.env().caller();
+            let member_groups = self.member_groups.get(caller).unwrap_or(Vec::<u128>::new());
+
+            let mut member_settlements = Vec::<Settlement>::new();
+            for group_id in member_groups {
+                let group_settlements = self
+                    .group_settlements
+                    .get(group_id)
+                    .unwrap_or(Vec::<Settlement>::new());
+                member_settlements.extend(
+                    group_settlements
+                        .into_iter()
+                        .filter(|s| s.giver == caller || s.taker == caller),
+                );
+            }
+
+            Ok(paginate(member_settlements, offset, limit))
+        }
+
+        /// Gets an expense of the specified group along with its refunds.
+        #[ink(message)]
+        pub fn get_expense(
+            &self,
+            group_id: u128,
+            expense_id: u32,
+        ) -> Result<ExpenseDetails, ContractError> {
+            check_group_membership(&self, group_id)?;
+            let group_expenses = self
+                .group_expenses
+                .get(group_id)
+                .unwrap_or(Vec::<Expense>::new());
+
+            let expense = group_expenses
+                .iter()
+                .find(|e| e.id == expense_id)
+                .ok_or(ContractError::ExpenseDoesNotExist)?
+                .clone();
+            let refunds = group_expenses
+                .into_iter()
+                .filter(|e| e.is_refund_of(expense_id))
+                .collect();
+
+            Ok(ExpenseDetails { expense, refunds })
+        }
+
+        /// Contributes ERC20 tokens from the caller to the group pot.
+        /// Checks if the caller is in the specified group.
+        #[ink(message)]
+        pub fn contribute_to_pot(&mut self, group_id: u128, amount: u128) -> BaseResult {
+            let caller = self.env().caller();
+            let contract_address = self.env().account_id();
+            check_group_membership(&self, group_id)?;
+            if amount == 0 {
+                return Err(ContractError::PotContributionIsZero);
+            }
+
+            PSP22Ref::transfer_from(
+                &mut self.token_address,
+                caller,
+                contract_address,
+                amount,
+                Vec::new(),
+            )
+            .map_err(ContractError::from)?;
+
+            let mut group_pot = self
+                .group_pots
+                .get(group_id)
+                .unwrap_or(GroupPot::new(group_id));
+            group_pot.add_contribution(caller, amount)?;
+            self.group_pots.insert(group_id, &group_pot);
+
+            Ok(())
+        }
+
+        /// Approves a member to spend from the group pot.
+        /// Checks if the caller is one of the group admins and if the payer is in the group.
+        #[ink(message)]
+        pub fn approve_pot_payer(&mut self, group_id: u128, payer: AccountId) -> BaseResult {
+            check_group_admin(&self, group_id)?;
+            check_member_group_membership(&self, payer, group_id)?;
+
+            let mut group_pot = self
+                .group_pots
+                .get(group_id)
+                .unwrap_or(GroupPot::new(group_id));
+            if !group_pot.is_approved_payer(payer) {
+                group_pot.approved_payers.push(payer);
+            }
+            self.group_pots.insert(group_id, &group_pot);
+
+            Ok(())
+        }
+
+        /// Revokes the approval of a member to spend from the group pot.
+        /// Checks if the caller is one of the group admins.
+        #[ink(message)]
+        pub fn revoke_pot_payer(&mut self, group_id: u128, payer: AccountId) -> BaseResult {
+            check_group_admin(&self, group_id)?;
+
+            let mut group_pot = self
+                .group_pots
+                .get(group_id)
+                .unwrap_or(GroupPot::new(group_id));
+            group_pot.approved_payers.retain(|p| *p != payer);
+            self.group_pots.insert(group_id, &group_pot);
+
+            Ok(())
+        }
+
+        /// Spends from the group pot, transferring ERC20 tokens to the recipient.
+        /// Checks if the caller is one of the group admins or an approved payer.
+        /// Adds an expense paid by the contributors (proportionally) and distributed between the specified members.
+        #[ink(message)]
+        pub fn spend_from_pot(&mut self, spend: PotSpendInput) -> BaseResult {
+            let caller = self.env().caller();
+            let group = check_group_membership(&self, spend.group_id)?;
+            let mut group_pot = self
+                .group_pots
+                .get(spend.group_id)
+                .unwrap_or(GroupPot::new(spend.group_id));
+            if !group.is_admin(caller) && !group_pot.is_approved_payer(caller) {
+                return Err(ContractError::CallerIsNotAPotPayer);
+            }
+
+            let recipient = spend.recipient;
+            let expense = build_pot_spend_expense(&mut group_pot, caller, spend)?;
+            let amount = expense.amount;
+
+            self.group_pots.insert(group.id, &group_pot);
+            add_group_expenses(self, caller, [expense].to_vec())?;
+
+            PSP22Ref::transfer(&mut self.token_address, recipient, amount, Vec::new())
+                .map_err(ContractError::from)?;
+
+            Ok(())
+        }
+
+        /// Refunds part of a member contribution from the group pot, transferring ERC20 tokens back to the member.
+        /// Checks if the caller is one of the group admins.
+        #[ink(message)]
+        pub fn refund_pot_contribution(
+            &mut self,
+            group_id: u128,
+            member_address: AccountId,
+            amount: u128,
+        ) -> BaseResult {
+            check_group_admin(&self, group_id)?;
+            if amount == 0 {
+                return Err(ContractError::PotContributionIsZero);
+            }
+
+            let mut group_pot = self
+                .group_pots
+                .get(group_id)
+                .unwrap_or(GroupPot::new(group_id));
+            group_pot.remove_contribution(member_address, amount)?;
+            self.group_pots.insert(group_id, &group_pot);
+
+            PSP22Ref::transfer(&mut self.token_address, member_address, amount, Vec::new())
+                .map_err(ContractError::from)?;
+
+            Ok(())
+        }
+
+        /// Gets the pot of the specified group.
+        #[ink(message)]
+        pub fn get_group_pot(&self, group_id: u128) -> Result<GroupPot, ContractError> {
+            check_group_membership(&self, group_id)?;
+            Ok(self
+                .group_pots
+                .get(group_id)
+                .unwrap_or(GroupPot::new(group_id)))
+        }
+
+        /// Sets the time (in milliseconds) to settle the debts of the group expenses without their own deadline.
+        /// Checks if the caller is one of the group admins.
+        /// Applies to the expenses added from now on.
+        #[ink(message)]
+        pub fn set_group_settle_by_term(
+            &mut self,
+            group_id: u128,
+            settle_by_term: Option<u64>,
+        ) -> BaseResult {
+            let mut group = check_group_admin(&self, group_id)?;
+
+            group.settle_by_term = settle_by_term;
+            self.groups.insert(group_id, &group);
+
+            Ok(())
+        }
+
+        /// Gets the overdue debts of the members of the specified group.
+        #[ink(message)]
+        pub fn get_overdue_debts(&self, group_id: u128) -> Result<Vec<OverdueDebt>, ContractError> {
+            let group = check_group_membership(&self, group_id)?;
+            get_group_overdue_debts(&self, &group)
+        }
+
+        /// Checks the overdue debts of the members of the specified group.
+        /// Can be called by any account (e.g. to schedule reminders).
+        /// Emits an event for every member with overdue debts.
+        #[ink(message)]
+        pub fn check_overdue(&mut self, group_id: u128) -> Result<Vec<OverdueDebt>, ContractError> {
+            let group = get_group_by_id(&self, group_id)?;
+            let overdue_debts = get_group_overdue_debts(&self, &group)?;
+
+            for overdue_debt in overdue_debts.iter() {
+                self.env().emit_event(DebtOverdue {
+                    group_id,
+                    member_account: overdue_debt.member_account,
+                    overdue_amount: overdue_debt.overdue_amount,
+                    overdue_since: overdue_debt.overdue_since,
+                    age: overdue_debt.age,
+                });
+            }
+
+            Ok(overdue_debts)
+        }
+
+        /// Sets the budget of a group, replacing the previous one.
+        /// Checks if the caller is one of the group admins.
+        /// The first budget period (if any) starts now.
+        #[ink(message)]
+        pub fn set_group_budget(
+            &mut self,
+            group_id: u128,
+            budget_to_set: GroupBudgetInput,
+        ) -> BaseResult {
+            let mut group = check_group_admin(&self, group_id)?;
+
+            group.budget = Some(build_group_budget(&self, budget_to_set)?);
+            self.groups.insert(group_id, &group);
+
+            Ok(())
+        }
+
+        /// Removes the budget of a group.
+        /// Checks if the caller is one of the group admins.
+        #[ink(message)]
+        pub fn remove_group_budget(&mut self, group_id: u128) -> BaseResult {
+            let mut group = check_group_admin(&self, group_id)?;
+
+            group.budget = None;
+            self.groups.insert(group_id, &group);
+
+            Ok(())
+        }
+
+        /// Gets the limit, spending and remaining amount of every part of the group budget.
+        #[ink(message)]
+        pub fn get_group_budget_status(
+            &self,
+            group_id: u128,
+        ) -> Result<GroupBudgetStatus, ContractError> {
+            let group = check_group_membership(&self, group_id)?;
+            let stats = self
+                .group_stats
+                .get(group_id)
+                .unwrap_or(GroupStats::new(group_id));
+
+            get_group_budget_status(&self, &group, &stats)
+        }
+
+        /// Gets the spending statistics of the specified group.
+        /// Includes the total spent, the amounts paid and consumed by member and the totals by category.
+        #[ink(message)]
+        pub fn get_group_stats(&self, group_id: u128) -> Result<GroupStats, ContractError> {
+            check_group_membership(&self, group_id)?;
+            Ok(self
+                .group_stats
+                .get(group_id)
+                .unwrap_or(GroupStats::new(group_id)))
+        }
+
+        /// Gets all the expenses of the specified group.
+        #[ink(message)]
+        pub fn get_expenses_by_group(&self, group_id: u128) -> Result<Vec<Expense>, ContractError> {
+            check_group_membership(&self, group_id)?;
+            Ok(self
+                .group_expenses
+                .get(group_id)
+                .unwrap_or(Vec::<Expense>::new()))
         }
     }
 }
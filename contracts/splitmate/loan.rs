@@ -0,0 +1,140 @@
+use crate::errors::ContractError;
+use crate::utils::BaseResult;
+use ink::prelude::vec::Vec;
+use ink::primitives::AccountId;
+use ink::storage::traits::StorageLayout;
+
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct LoanInstalment {
+    /// The block timestamp when the instalment is due
+    pub due_at: u64,
+    pub amount: u128,
+    pub paid: u128,
+}
+
+/// Each loan is an IOU from a borrower to a lender inside a group, kept apart from the group balances.
+/// The amount due is the principal plus the interest (in basis points), split in instalments.
+/// The repayments pay the instalments in order.
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct Loan {
+    pub id: u32,
+    pub group_id: u128,
+    pub lender: AccountId,
+    pub borrower: AccountId,
+    pub principal: u128,
+    pub interest_bps: u32,
+    pub total_due: u128,
+    pub repaid: u128,
+    pub instalments: Vec<LoanInstalment>,
+    pub created_at: u64,
+}
+
+impl Loan {
+    pub fn outstanding(&self) -> u128 {
+        self.total_due.saturating_sub(self.repaid)
+    }
+
+    /// Gets the principal part of the outstanding amount (the repayments pay principal and interest proportionally).
+    pub fn outstanding_principal(&self) -> Result<u128, ContractError> {
+        if self.total_due == 0 {
+            return Ok(0);
+        }
+
+        Ok(self
+            .principal
+            .checked_mul(self.outstanding())
+            .ok_or(ContractError::Overflow)?
+            / self.total_due)
+    }
+
+    pub fn next_instalment(&self) -> Option<LoanInstalment> {
+        self.instalments
+            .iter()
+            .find(|instalment| instalment.paid < instalment.amount)
+            .cloned()
+    }
+
+    /// Pays the instalments in order with the repaid amount.
+    pub fn repay(&mut self, amount: u128) -> BaseResult {
+        if amount == 0 {
+            return Err(ContractError::LoanRepaymentIsZero);
+        }
+        if amount > self.outstanding() {
+            return Err(ContractError::LoanRepaymentExceedsOutstanding);
+        }
+
+        let mut pending_amount = amount;
+        for instalment in self.instalments.iter_mut() {
+            let paid_amount = pending_amount.min(instalment.amount - instalment.paid);
+            instalment.paid += paid_amount;
+            pending_amount -= paid_amount;
+        }
+
+        self.repaid = self
+            .repaid
+            .checked_add(amount)
+            .ok_or(ContractError::Overflow)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn loan(principal: u128, total_due: u128, instalment_amounts: &[u128]) -> Loan {
+        Loan {
+            id: 1,
+            group_id: 1,
+            lender: AccountId::from([2; 32]),
+            borrower: AccountId::from([1; 32]),
+            principal,
+            interest_bps: 0,
+            total_due,
+            repaid: 0,
+            instalments: instalment_amounts
+                .iter()
+                .map(|amount| LoanInstalment {
+                    due_at: 0,
+                    amount: *amount,
+                    paid: 0,
+                })
+                .collect(),
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn repay_pays_the_instalments_in_order() {
+        let mut loan = loan(90, 100, &[50, 50]);
+
+        loan.repay(70).unwrap();
+
+        assert_eq!(loan.outstanding(), 30);
+        assert_eq!(loan.outstanding_principal(), Ok(27));
+        assert_eq!(loan.instalments[0].paid, 50);
+        assert_eq!(loan.next_instalment().map(|i| i.paid), Some(20));
+    }
+
+    #[test]
+    fn repay_rejects_zero_and_amounts_over_the_outstanding() {
+        let mut loan = loan(100, 100, &[100]);
+
+        assert_eq!(loan.repay(0), Err(ContractError::LoanRepaymentIsZero));
+        assert_eq!(
+            loan.repay(101),
+            Err(ContractError::LoanRepaymentExceedsOutstanding)
+        );
+        assert_eq!(loan.repaid, 0);
+    }
+
+    #[test]
+    fn outstanding_principal_fails_on_overflow() {
+        let loan = loan(u128::MAX, u128::MAX, &[u128::MAX]);
+
+        assert_eq!(loan.outstanding_principal(), Err(ContractError::Overflow));
+    }
+}
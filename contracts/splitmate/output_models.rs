@@ -1,4 +1,8 @@
+use crate::budget::{BudgetEnforcement, BudgetScope};
+use crate::errors::ContractError;
+use crate::expense::Expense;
 use crate::group::Group;
+use crate::loan::{Loan, LoanInstalment};
 use ink::prelude::vec::Vec;
 use ink::primitives::AccountId;
 use ink::storage::traits::StorageLayout;
@@ -27,21 +31,141 @@ pub struct GroupMemberDistributionTransfer {
 
 #[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
 #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
-pub struct GroupSettledDebts {
+pub enum PaymentStatus {
+    Settled,
+    /// The payment was not attempted (invalid or after a failed payment)
+    Skipped,
+    Failed,
+}
+
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct SettleUpPayment {
     pub group_id: u128,
-    pub takers: Vec<AccountId>,
+    pub taker: AccountId,
+    pub amount: u128,
+    pub status: PaymentStatus,
+    /// The reason why the payment was skipped or failed
+    pub error: Option<ContractError>,
 }
 
 #[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
 #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
 pub struct SettleUpResult {
+    /// True if all the payments were settled
+    pub result: bool,
+    pub payments: Vec<SettleUpPayment>,
+}
+
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct SettleUpSimulation {
+    /// True if all the payments would be settled
     pub result: bool,
-    pub total_settled_debts: Option<Vec<GroupSettledDebts>>,
+    pub payments: Vec<SettleUpPayment>,
+    /// The giver token balance
+    pub balance: u128,
+    /// The giver token allowance to the contract
+    pub allowance: u128,
+    /// The groups with the balances after the settlement
+    pub projected_groups: Vec<Group>,
 }
 
 #[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
 #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
 pub struct GroupDistributionByMember {
     pub group_id: u128,
-    pub member_distribution: GroupMemberDistribution,
+    /// The member balance/debt in the group
+    pub balance: i128,
+    /// Transfers the member has to do to other members
+    pub debts: Vec<GroupMemberDistributionTransfer>,
+    /// Transfers the member has to receive from other members
+    pub credits: Vec<GroupMemberDistributionTransfer>,
+}
+
+/// The balance between two members inside a group.
+/// Positive if the member owes the counterpart, negative if the counterpart owes the member.
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct GroupPairBalance {
+    pub group_id: u128,
+    pub value: i128,
+}
+
+/// The balance between two members netted across all their shared groups.
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct MemberNetBalance {
+    pub member_account: AccountId,
+    pub net_value: i128,
+    pub balances_by_group: Vec<GroupPairBalance>,
+}
+
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct MemberBalanceDiscrepancy {
+    pub member_account: AccountId,
+    /// The balance/debt stored in the group
+    pub stored_balance: i128,
+    /// The balance/debt recomputed from the expenses and settlements
+    pub replayed_balance: i128,
+}
+
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct GroupAudit {
+    pub group_id: u128,
+    pub is_consistent: bool,
+    pub discrepancies: Vec<MemberBalanceDiscrepancy>,
+}
+
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct AddedExpense {
+    pub group_id: u128,
+    pub expense_id: u32,
+}
+
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct ExpenseDetails {
+    pub expense: Expense,
+    pub refunds: Vec<Expense>,
+}
+
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct BudgetUsage {
+    pub scope: BudgetScope,
+    pub limit: u128,
+    pub spent: u128,
+    pub remaining: u128,
+}
+
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct GroupBudgetStatus {
+    pub group_id: u128,
+    pub enforcement: BudgetEnforcement,
+    pub usages: Vec<BudgetUsage>,
+}
+
+/// The part of a member debt with a past settle-by deadline.
+/// The age is the time since the oldest overdue expense was added.
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct OverdueDebt {
+    pub member_account: AccountId,
+    pub overdue_amount: u128,
+    pub overdue_since: u64,
+    pub age: u64,
+}
+
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct LoanDetails {
+    pub loan: Loan,
+    pub outstanding: u128,
+    pub outstanding_principal: u128,
+    pub next_instalment: Option<LoanInstalment>,
 }
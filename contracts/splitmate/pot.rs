@@ -0,0 +1,80 @@
+use crate::errors::ContractError;
+use crate::utils::BaseResult;
+use ink::prelude::vec::Vec;
+use ink::primitives::AccountId;
+use ink::storage::traits::StorageLayout;
+
+/// The amount of the pot balance that belongs to a member.
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct PotContribution {
+    pub address: AccountId,
+    pub amount: u128,
+}
+
+/// Each group pot keeps the tokens contributed by the members before a purchase.
+/// The admins and the approved payers can spend from the pot.
+/// The spends are taken from every contribution proportionally.
+/// The admins can refund the remaining contributions to their members.
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct GroupPot {
+    pub group_id: u128,
+    pub balance: u128,
+    pub contributions: Vec<PotContribution>,
+    pub approved_payers: Vec<AccountId>,
+}
+
+impl GroupPot {
+    pub fn new(group_id: u128) -> GroupPot {
+        GroupPot {
+            group_id,
+            balance: 0,
+            contributions: Vec::<PotContribution>::new(),
+            approved_payers: Vec::<AccountId>::new(),
+        }
+    }
+
+    pub fn is_approved_payer(&self, address: AccountId) -> bool {
+        self.approved_payers.iter().any(|payer| *payer == address)
+    }
+
+    pub fn add_contribution(&mut self, address: AccountId, amount: u128) -> BaseResult {
+        match self.contributions.iter_mut().find(|c| c.address == address) {
+            Some(contribution) => {
+                contribution.amount = contribution
+                    .amount
+                    .checked_add(amount)
+                    .ok_or(ContractError::Overflow)?;
+            }
+            None => self.contributions.push(PotContribution { address, amount }),
+        }
+
+        self.balance = self
+            .balance
+            .checked_add(amount)
+            .ok_or(ContractError::Overflow)?;
+
+        Ok(())
+    }
+
+    pub fn remove_contribution(&mut self, address: AccountId, amount: u128) -> BaseResult {
+        let contribution = self
+            .contributions
+            .iter_mut()
+            .find(|c| c.address == address)
+            .ok_or(ContractError::PotContributionIsNotEnough)?;
+        contribution.amount = contribution
+            .amount
+            .checked_sub(amount)
+            .ok_or(ContractError::PotContributionIsNotEnough)?;
+        self.contributions.retain(|c| c.amount > 0);
+
+        self.balance = self
+            .balance
+            .checked_sub(amount)
+            .ok_or(ContractError::Overflow)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,44 @@
+use crate::input_models::ExpenseInput;
+use ink::primitives::AccountId;
+use ink::storage::traits::StorageLayout;
+
+/// The unit used to schedule a recurring expense.
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub enum RecurrenceUnit {
+    /// Block numbers
+    BLOCKS,
+    /// Block timestamps in milliseconds
+    TIMESTAMP,
+}
+
+/// Each recurring expense is a template linked to a group.
+/// An expense is created from the template every period, until the end (if any).
+/// The period, next occurrence and end are expressed in the recurrence unit.
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct RecurringExpense {
+    pub id: u32,
+    pub group_id: u128,
+    pub created_by: AccountId,
+    pub expense: ExpenseInput,
+    pub unit: RecurrenceUnit,
+    pub period: u64,
+    pub next_occurrence: u64,
+    pub ends_at: Option<u64>,
+}
+
+impl RecurringExpense {
+    /// Checks if the next occurrence is due at the specified moment.
+    pub fn is_due(&self, now: u64) -> bool {
+        self.next_occurrence <= now && !self.has_ended()
+    }
+
+    /// Checks if the next occurrence is after the end of the recurrence.
+    pub fn has_ended(&self) -> bool {
+        match self.ends_at {
+            Some(ends_at) => self.next_occurrence > ends_at,
+            None => false,
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use ink::primitives::AccountId;
+use ink::storage::traits::StorageLayout;
+
+#[allow(non_camel_case_types)]
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub enum SettlementKind {
+    /// The giver transferred the tokens to the taker
+    PAYMENT,
+    /// The taker forgave the debt of the giver, without any token transfer
+    FORGIVENESS,
+    /// The debt was offset by debts between the same members in other groups, without any token transfer
+    NETTING,
+    /// The giver (borrower) transferred the tokens to the taker (lender) to repay a loan
+    LOAN_REPAYMENT { loan_id: u32 },
+}
+
+/// Each settlement is a payment (a forgiveness or a netting) from a giver to a taker inside a group.
+/// Keeps the token used for the payment and when it was done.
+/// The loan repayments are kept apart from the group balances.
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct Settlement {
+    pub group_id: u128,
+    pub kind: SettlementKind,
+    pub giver: AccountId,
+    pub taker: AccountId,
+    pub amount: u128,
+    pub token_address: AccountId,
+    pub block_number: u32,
+    pub timestamp: u64,
+}
+
+impl Settlement {
+    pub fn affects_balances(&self) -> bool {
+        !matches!(self.kind, SettlementKind::LOAN_REPAYMENT { .. })
+    }
+}
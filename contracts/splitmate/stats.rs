@@ -0,0 +1,74 @@
+use ink::prelude::{string::String, vec::Vec};
+use ink::primitives::AccountId;
+use ink::storage::traits::StorageLayout;
+
+/// Amounts paid and consumed by a member across the group expenses.
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct MemberStats {
+    pub address: AccountId,
+    pub paid: u128,
+    pub consumed: u128,
+}
+
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct CategoryStats {
+    pub category: String,
+    pub total: u128,
+}
+
+/// Spending statistics of a group, updated every time an expense is added.
+/// Refunds are discounted and transfers between members are not considered spending.
+#[derive(PartialEq, Debug, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct GroupStats {
+    pub group_id: u128,
+    pub total_spent: u128,
+    pub members: Vec<MemberStats>,
+    pub categories: Vec<CategoryStats>,
+}
+
+impl GroupStats {
+    pub fn new(group_id: u128) -> GroupStats {
+        GroupStats {
+            group_id,
+            total_spent: 0,
+            members: Vec::<MemberStats>::new(),
+            categories: Vec::<CategoryStats>::new(),
+        }
+    }
+
+    /// Gets the member stats, adding them if the member has none yet.
+    pub fn member_mut(&mut self, address: AccountId) -> &mut MemberStats {
+        let member_index = match self.members.iter().position(|m| m.address == address) {
+            Some(index) => index,
+            None => {
+                self.members.push(MemberStats {
+                    address,
+                    paid: 0,
+                    consumed: 0,
+                });
+                self.members.len() - 1
+            }
+        };
+
+        &mut self.members[member_index]
+    }
+
+    /// Gets the category stats, adding them if the category has none yet.
+    pub fn category_mut(&mut self, category: &String) -> &mut CategoryStats {
+        let category_index = match self.categories.iter().position(|c| &c.category == category) {
+            Some(index) => index,
+            None => {
+                self.categories.push(CategoryStats {
+                    category: category.clone(),
+                    total: 0,
+                });
+                self.categories.len() - 1
+            }
+        };
+
+        &mut self.categories[category_index]
+    }
+}
@@ -1,20 +1,382 @@
-use ink::codegen::Env;
+use ink::codegen::{EmitEvent, Env};
+use ink::env::hash::{Blake2x256, HashOutput};
 use ink::prelude::vec::Vec;
 use ink::primitives::AccountId;
+use openbrush::contracts::traits::psp22::PSP22Ref;
 
 use crate::{
+    assignment::DebtAssignment,
+    budget::{BudgetEnforcement, BudgetScope, GroupBudget, PeriodBudget},
+    delegation::DelegatedAction,
+    dispute::DisputeResolution,
     errors::ContractError,
-    expense::{DistributionType, Expense, ExpenseMember},
+    expense::{DistributionType, Expense, ExpenseKind, ExpenseMember, ExpenseStatus},
     group::{Group, GroupMember},
+    input_models::{
+        DistributionByMemberInput, DistributionInput, ExpenseInput, GroupBudgetInput,
+        GroupDebtsToPay, ItemizedExpenseInput, LoanInput, PotSpendInput, RefundInput,
+    },
+    loan::{Loan, LoanInstalment},
     output_models::{
-        GroupDistributionByMember, GroupMemberDistribution, GroupMemberDistributionTransfer,
+        AddedExpense, BudgetUsage, GroupAudit, GroupBudgetStatus, GroupDistributionByMember,
+        GroupMemberDistribution, GroupMemberDistributionTransfer, GroupPairBalance,
+        MemberBalanceDiscrepancy, MemberNetBalance, OverdueDebt, PaymentStatus, SettleUpPayment,
+        SettleUpResult, SettleUpSimulation,
+    },
+    pot::GroupPot,
+    recurring::{RecurrenceUnit, RecurringExpense},
+    settlement::{Settlement, SettlementKind},
+    splitmate::{
+        AutoSettleRequired, BudgetThresholdReached, ExpenseOverBudget, LoanRepaid,
+        RecurringExpenseFailed, Splitmate,
     },
-    splitmate::Splitmate,
+    stats::GroupStats,
 };
 
 pub type BaseResult = Result<(), ContractError>;
 
+/// Maximum number of occurrences materialized by recurring expense in a single call
+const MAX_RECURRING_OCCURRENCES_PER_CALL: u32 = 12;
+
+/// Basis points of the whole principal (100%)
+const LOAN_INTEREST_BPS_BASE: u128 = 10_000;
+
+/// Budget usage percentages that emit an event when crossed
+const BUDGET_THRESHOLDS: [u8; 2] = [80, 100];
+
+const GUEST_ADDRESS_PREFIX: &[u8] = b"splitmate-guest";
+
+/// Gets the items from the offset position, up to the limit.
+pub fn paginate<T>(items: Vec<T>, offset: u32, limit: u32) -> Vec<T> {
+    items
+        .into_iter()
+        .skip(offset as usize)
+        .take(limit as usize)
+        .collect()
+}
+
+/// Converts an unsigned amount into a signed balance without wrapping.
+pub fn to_balance(amount: u128) -> Result<i128, ContractError> {
+    i128::try_from(amount).map_err(|_| ContractError::AmountTooLarge)
+}
+
+/// Updates the group spending statistics with an expense.
+/// Refunds are discounted from the totals and transfers are ignored.
+pub fn update_group_stats(stats: &mut GroupStats, expense: &Expense) -> BaseResult {
+    apply_expense_to_group_stats(stats, expense, false)
+}
+
+/// Removes an expense from the group spending statistics.
+pub fn revert_group_stats(stats: &mut GroupStats, expense: &Expense) -> BaseResult {
+    apply_expense_to_group_stats(stats, expense, true)
+}
+
+fn apply_expense_to_group_stats(
+    stats: &mut GroupStats,
+    expense: &Expense,
+    is_reverted: bool,
+) -> BaseResult {
+    // Refunds are discounted, unless they are reverted
+    let is_refund = match expense.kind {
+        ExpenseKind::EXPENSE => is_reverted,
+        ExpenseKind::REFUND { .. } => !is_reverted,
+        ExpenseKind::TRANSFER => return Ok(()),
+    };
+
+    for expense_member in expense.members.iter() {
+        let consumed = calculate_amount_to_pay_by_member(expense, expense_member.clone())?;
+        let member_stats = stats.member_mut(expense_member.address);
+
+        if is_refund {
+            // A refund can be credited to a member who did not consume the original expense
+            member_stats.paid = member_stats.paid.saturating_sub(expense_member.paid);
+            member_stats.consumed = member_stats.consumed.saturating_sub(consumed);
+        } else {
+            member_stats.paid = member_stats
+                .paid
+                .checked_add(expense_member.paid)
+                .ok_or(ContractError::Overflow)?;
+            member_stats.consumed = member_stats
+                .consumed
+                .checked_add(consumed)
+                .ok_or(ContractError::Overflow)?;
+        }
+    }
+
+    if is_refund {
+        stats.total_spent = stats.total_spent.saturating_sub(expense.amount);
+    } else {
+        stats.total_spent = stats
+            .total_spent
+            .checked_add(expense.amount)
+            .ok_or(ContractError::Overflow)?;
+    }
+
+    if let Some(category) = &expense.category {
+        let category_stats = stats.category_mut(category);
+        if is_refund {
+            category_stats.total = category_stats.total.saturating_sub(expense.amount);
+        } else {
+            category_stats.total = category_stats
+                .total
+                .checked_add(expense.amount)
+                .ok_or(ContractError::Overflow)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Builds a group budget starting the first period (if any) now.
+pub fn build_group_budget(
+    instance: &Splitmate,
+    budget_to_set: GroupBudgetInput,
+) -> Result<GroupBudget, ContractError> {
+    let period = match budget_to_set.period {
+        Some(period) => {
+            if period.length == 0 {
+                return Err(ContractError::BudgetPeriodIsZero);
+            }
+
+            Some(PeriodBudget {
+                current_period_start: get_recurrence_now(instance, &period.unit),
+                current_period_spent: 0,
+                unit: period.unit,
+                length: period.length,
+                limit: period.limit,
+            })
+        }
+        None => None,
+    };
+
+    Ok(GroupBudget {
+        total: budget_to_set.total,
+        categories: budget_to_set.categories,
+        period,
+        enforcement: budget_to_set.enforcement,
+    })
+}
+
+/// Gets the limit, spending and remaining amount of every part of the group budget.
+pub fn get_group_budget_status(
+    instance: &Splitmate,
+    group: &Group,
+    stats: &GroupStats,
+) -> Result<GroupBudgetStatus, ContractError> {
+    let budget = group
+        .budget
+        .as_ref()
+        .ok_or(ContractError::GroupDoesNotHaveBudget)?;
+    let mut usages = Vec::<BudgetUsage>::new();
+
+    if let Some(limit) = budget.total {
+        usages.push(build_budget_usage(
+            BudgetScope::Total,
+            limit,
+            stats.total_spent,
+        ));
+    }
+    for category_budget in budget.categories.iter() {
+        let spent = stats
+            .categories
+            .iter()
+            .find(|c| c.category == category_budget.category)
+            .map(|c| c.total)
+            .unwrap_or(0);
+        usages.push(build_budget_usage(
+            BudgetScope::Category(category_budget.category.clone()),
+            category_budget.limit,
+            spent,
+        ));
+    }
+    if let Some(period) = &budget.period {
+        let now = get_recurrence_now(instance, &period.unit);
+        usages.push(build_budget_usage(
+            BudgetScope::Period,
+            period.limit,
+            period.spent_at(now),
+        ));
+    }
+
+    Ok(GroupBudgetStatus {
+        group_id: group.id,
+        enforcement: budget.enforcement.clone(),
+        usages,
+    })
+}
+
+pub fn build_budget_usage(scope: BudgetScope, limit: u128, spent: u128) -> BudgetUsage {
+    BudgetUsage {
+        scope,
+        limit,
+        spent,
+        remaining: limit.saturating_sub(spent),
+    }
+}
+
+/// Checks an expense against the group budget (if any), before updating the group stats.
+/// Rejects the expense or flags it with an event if it exceeds a limit, depending on the budget enforcement.
+/// Emits an event for every usage threshold crossed by the expense.
+/// Updates the spending of the current budget period.
+/// Refunds are not checked and are discounted from the current period spending.
+/// Transfers are not checked.
+pub fn apply_group_budget(
+    instance: &Splitmate,
+    group: &mut Group,
+    stats: &GroupStats,
+    expense: &Expense,
+) -> BaseResult {
+    match expense.kind {
+        ExpenseKind::EXPENSE => (),
+        ExpenseKind::REFUND { .. } => {
+            return update_group_budget_period(instance, group, expense.amount, true);
+        }
+        ExpenseKind::TRANSFER => return Ok(()),
+    }
+    let budget = match group.budget.as_mut() {
+        Some(budget) => budget,
+        None => return Ok(()),
+    };
+
+    // Every limit affected by the expense, along with the spending before the expense
+    let mut limits = Vec::<(BudgetScope, u128, u128)>::new();
+    if let Some(limit) = budget.total {
+        limits.push((BudgetScope::Total, limit, stats.total_spent));
+    }
+    if let Some(category) = &expense.category {
+        if let Some(limit) = budget.category_limit(category) {
+            let spent = stats
+                .categories
+                .iter()
+                .find(|c| &c.category == category)
+                .map(|c| c.total)
+                .unwrap_or(0);
+            limits.push((BudgetScope::Category(category.clone()), limit, spent));
+        }
+    }
+    if let Some(period) = budget.period.as_mut() {
+        period.roll_to(get_recurrence_now(instance, &period.unit));
+
+        limits.push((
+            BudgetScope::Period,
+            period.limit,
+            period.current_period_spent,
+        ));
+        period.current_period_spent = period
+            .current_period_spent
+            .checked_add(expense.amount)
+            .ok_or(ContractError::Overflow)?;
+    }
+
+    // Checks every limit before informing anything
+    for (_, limit, spent_before) in limits.iter() {
+        let spent = spent_before
+            .checked_add(expense.amount)
+            .ok_or(ContractError::Overflow)?;
+
+        if spent > *limit && budget.enforcement == BudgetEnforcement::REJECT {
+            return Err(ContractError::GroupBudgetExceeded);
+        }
+    }
+
+    for (scope, limit, spent_before) in limits {
+        let spent = spent_before
+            .checked_add(expense.amount)
+            .ok_or(ContractError::Overflow)?;
+
+        for threshold in BUDGET_THRESHOLDS {
+            let threshold_amount = limit
+                .checked_mul(threshold as u128)
+                .ok_or(ContractError::Overflow)?
+                / 100;
+
+            if spent_before < threshold_amount && spent >= threshold_amount {
+                instance.env().emit_event(BudgetThresholdReached {
+                    group_id: group.id,
+                    scope: scope.clone(),
+                    threshold,
+                    spent,
+                    limit,
+                });
+            }
+        }
+
+        if spent > limit {
+            instance.env().emit_event(ExpenseOverBudget {
+                group_id: group.id,
+                expense_id: expense.id,
+                scope,
+                spent,
+                limit,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Adds or discounts an amount to/from the spending of the current budget period (if any), without checking the limit.
+/// The total and category spending are taken from the group stats.
+pub fn update_group_budget_period(
+    instance: &Splitmate,
+    group: &mut Group,
+    amount: u128,
+    is_discounted: bool,
+) -> BaseResult {
+    let period = match group
+        .budget
+        .as_mut()
+        .and_then(|budget| budget.period.as_mut())
+    {
+        Some(period) => period,
+        None => return Ok(()),
+    };
+
+    period.roll_to(get_recurrence_now(instance, &period.unit));
+    period.current_period_spent = if is_discounted {
+        period.current_period_spent.saturating_sub(amount)
+    } else {
+        period
+            .current_period_spent
+            .checked_add(amount)
+            .ok_or(ContractError::Overflow)?
+    };
+
+    Ok(())
+}
+
+/// Checks if an expense was added in the current budget period (if any).
+/// Only the periods expressed in timestamps can be checked, since the expenses keep their timestamp.
+pub fn is_in_current_budget_period(instance: &Splitmate, group: &Group, expense: &Expense) -> bool {
+    match group
+        .budget
+        .as_ref()
+        .and_then(|budget| budget.period.as_ref())
+    {
+        Some(period) if period.unit == RecurrenceUnit::TIMESTAMP => {
+            expense.created_at >= period.period_start_at(get_recurrence_now(instance, &period.unit))
+        }
+        _ => false,
+    }
+}
+
+/// Checks if an expense has refunds not deleted.
+pub fn has_active_refunds(group_expenses: &[Expense], expense_id: u32) -> bool {
+    group_expenses
+        .iter()
+        .any(|e| e.is_refund_of(expense_id) && e.status != ExpenseStatus::DELETED)
+}
+
 pub fn process_expense_debts(group: &mut Group, expense: &Expense) -> BaseResult {
+    update_expense_debts(group, expense, false)
+}
+
+/// Removes the effect of an expense on the group balances.
+pub fn revert_expense_debts(group: &mut Group, expense: &Expense) -> BaseResult {
+    update_expense_debts(group, expense, true)
+}
+
+fn update_expense_debts(group: &mut Group, expense: &Expense, is_reverted: bool) -> BaseResult {
     for expense_distribution_member in expense.members.clone() {
         // Check/Get the group member reference and remove it
         let group_member_index = group
@@ -31,15 +393,28 @@ pub fn process_expense_debts(group: &mut Group, expense: &Expense) -> BaseResult
 
         // Calculate how much the member has to pay
         let amount_to_pay =
-            calculate_amount_to_pay_by_member(expense, expense_distribution_member.clone());
+            calculate_amount_to_pay_by_member(expense, expense_distribution_member.clone())?;
 
         // Calculate the difference between the amount the member has to pay and the amount the member paid
-        let debt = (amount_to_pay as i128)
-            .checked_sub(expense_distribution_member.paid as i128)
-            .unwrap();
+        // (a refund is reversed: the member received the paid amount and is credited the amount to pay)
+        let debt = match expense.kind {
+            ExpenseKind::REFUND { .. } => to_balance(expense_distribution_member.paid)?
+                .checked_sub(to_balance(amount_to_pay)?),
+            _ => to_balance(amount_to_pay)?
+                .checked_sub(to_balance(expense_distribution_member.paid)?),
+        }
+        .ok_or(ContractError::Overflow)?;
+        let debt = if is_reverted {
+            debt.checked_neg().ok_or(ContractError::Overflow)?
+        } else {
+            debt
+        };
 
         // Update the member debt
-        group_member.debt_value = group_member.debt_value.checked_add(debt).unwrap();
+        group_member.debt_value = group_member
+            .debt_value
+            .checked_add(debt)
+            .ok_or(ContractError::Overflow)?;
 
         group.members.push(group_member);
     }
@@ -51,37 +426,41 @@ pub fn process_expense_debts(group: &mut Group, expense: &Expense) -> BaseResult
 pub fn process_giver_debt(
     debt_value: u128,
     takers: &mut Vec<GroupMember>,
-) -> GroupMemberDistributionTransfer {
+) -> Result<GroupMemberDistributionTransfer, ContractError> {
     // With same balance
-    let taker_with_same_balance_amount =
-        find_taker_by(takers, |r| r.debt_value.abs() as u128 == debt_value);
-
-    if taker_with_same_balance_amount.is_some() {
-        return GroupMemberDistributionTransfer {
-            member_account: taker_with_same_balance_amount.unwrap(),
+    if let Some(taker) = find_taker_by(takers, |r| r.debt_value.unsigned_abs() == debt_value) {
+        return Ok(GroupMemberDistributionTransfer {
+            member_account: taker,
             value: debt_value,
-        };
+        });
     }
 
-    // With more balance
-    let taker_with_more_balance_amount =
-        find_taker_by(takers, |r| r.debt_value.abs() as u128 > debt_value);
+    // With more balance (the taker keeps the remaining balance for the next givers)
+    if let Some(taker) = takers
+        .iter_mut()
+        .find(|r| r.debt_value.unsigned_abs() > debt_value)
+    {
+        taker.debt_value = taker
+            .debt_value
+            .checked_add(to_balance(debt_value)?)
+            .ok_or(ContractError::Overflow)?;
 
-    if taker_with_more_balance_amount.is_some() {
-        return GroupMemberDistributionTransfer {
-            member_account: taker_with_more_balance_amount.unwrap(),
+        return Ok(GroupMemberDistributionTransfer {
+            member_account: taker.address,
             value: debt_value,
-        };
+        });
     }
 
     // With less balance
-    let taker = takers[0].clone();
-    takers.remove(0);
+    if takers.len() == 0 {
+        return Err(ContractError::MemberNotFound);
+    }
+    let taker = takers.remove(0);
 
-    GroupMemberDistributionTransfer {
+    Ok(GroupMemberDistributionTransfer {
         member_account: taker.address,
-        value: taker.debt_value.abs() as u128,
-    }
+        value: taker.debt_value.unsigned_abs(),
+    })
 }
 
 /// Updates the specified member group debt
@@ -90,31 +469,38 @@ pub fn update_member_group_debt(
     member_address: AccountId,
     is_taker: bool,
     amount: u128,
-) {
+) -> BaseResult {
     let member_position = group
         .members
         .iter()
         .position(|m| m.address == member_address)
-        .unwrap();
+        .ok_or(ContractError::MemberNotFound)?;
     let mut member = group.members[member_position].clone();
     group.members.remove(member_position);
 
+    let amount = to_balance(amount)?;
     member.debt_value = if is_taker {
-        member.debt_value.checked_add(amount as i128).unwrap()
+        member.debt_value.checked_add(amount)
     } else {
-        member.debt_value.checked_sub(amount as i128).unwrap()
-    };
+        member.debt_value.checked_sub(amount)
+    }
+    .ok_or(ContractError::Overflow)?;
 
-    group.members.push(member)
+    group.members.push(member);
+
+    Ok(())
 }
 
-pub fn calculate_amount_to_pay_by_member(expense: &Expense, split_member: ExpenseMember) -> u128 {
+pub fn calculate_amount_to_pay_by_member(
+    expense: &Expense,
+    split_member: ExpenseMember,
+) -> Result<u128, ContractError> {
     return match expense.distribution_type {
         DistributionType::EQUALLY => expense
             .amount
             .checked_div(expense.members.len() as u128)
-            .unwrap(),
-        DistributionType::UNEQUALLY => split_member.must_pay,
+            .ok_or(ContractError::ExpenseWithoutDistributionMembers),
+        DistributionType::UNEQUALLY => Ok(split_member.must_pay),
     };
 }
 
@@ -122,26 +508,28 @@ pub fn find_taker_by<P>(takers: &mut Vec<GroupMember>, predicate: P) -> Option<A
 where
     P: FnMut(&GroupMember) -> bool,
 {
-    let taker_index = takers.iter().position(predicate);
-
-    if taker_index.is_some() {
-        let taker = takers[taker_index.unwrap()].clone();
-        takers.remove(taker_index.unwrap());
-
-        return Some(taker.address);
-    }
+    let taker_index = takers.iter().position(predicate)?;
 
-    None
+    Some(takers.remove(taker_index).address)
 }
 
 /// Checks if the specified group exists and if the caller is inside of it.
 pub fn check_group_membership(
     instance: &Splitmate,
     group_id: u128,
+) -> Result<Group, ContractError> {
+    check_member_group_membership(instance, instance.env().caller(), group_id)
+}
+
+/// Checks if the specified group exists and if the member is inside of it.
+pub fn check_member_group_membership(
+    instance: &Splitmate,
+    member_address: AccountId,
+    group_id: u128,
 ) -> Result<Group, ContractError> {
     let group = get_group_by_id(instance, group_id)?;
 
-    return match instance.member_groups.get(instance.env().caller()) {
+    return match instance.member_groups.get(member_address) {
         Some(member_groups) => {
             if !member_groups
                 .iter()
@@ -156,6 +544,159 @@ pub fn check_group_membership(
     };
 }
 
+/// Checks if a debt can be paid by the giver to the taker inside the group.
+pub fn validate_debt_to_pay(
+    group: &Group,
+    giver: AccountId,
+    taker: &DistributionByMemberInput,
+) -> BaseResult {
+    if taker.value == 0 {
+        return Err(ContractError::SettlementAmountIsZero);
+    }
+
+    if taker.member_address == giver {
+        return Err(ContractError::SettlementTakerIsTheGiver);
+    }
+
+    let amount = to_balance(taker.value)?;
+
+    let taker_member = group
+        .members
+        .iter()
+        .find(|m| m.address == taker.member_address)
+        .ok_or(ContractError::MemberIsNotInTheGroup)?;
+    if taker_member.is_guest() {
+        return Err(ContractError::MemberIsAGuest);
+    }
+    if taker_member
+        .debt_value
+        .checked_add(amount)
+        .ok_or(ContractError::Overflow)?
+        > 0
+    {
+        return Err(ContractError::SettlementExceedsTakerCredit);
+    }
+
+    let giver_member = group
+        .members
+        .iter()
+        .find(|m| m.address == giver)
+        .ok_or(ContractError::MemberNotFound)?;
+    if giver_member
+        .debt_value
+        .checked_sub(amount)
+        .ok_or(ContractError::Overflow)?
+        < 0
+    {
+        return Err(ContractError::SettlementExceedsGiverDebt);
+    }
+
+    Ok(())
+}
+
+/// Updates the group debts for a payment from the giver to the taker.
+pub fn apply_group_payment(
+    group: &mut Group,
+    giver: AccountId,
+    taker: AccountId,
+    amount: u128,
+) -> BaseResult {
+    update_member_group_debt(group, giver, false, amount)?;
+    update_member_group_debt(group, taker, true, amount)
+}
+
+/// Simulates the settle up of the selected debts without changing the storage.
+/// Validates each debt against the current group balances.
+/// Checks the giver token balance and the allowance given to the contract.
+/// Projects the group balances after the settlement.
+pub fn simulate_group_debts_payments(
+    instance: &Splitmate,
+    giver: AccountId,
+    debts_to_pay: Vec<GroupDebtsToPay>,
+) -> Result<SettleUpSimulation, ContractError> {
+    let balance = PSP22Ref::balance_of(&instance.token_address, giver);
+    let allowance =
+        PSP22Ref::allowance(&instance.token_address, giver, instance.env().account_id());
+
+    let mut remaining_balance = balance;
+    let mut remaining_allowance = allowance;
+    let mut payments = Vec::<SettleUpPayment>::new();
+    let mut projected_groups = Vec::<Group>::new();
+    let mut has_failed = false;
+
+    for group_debts_to_pay in debts_to_pay {
+        // Reuse the projected group if it was already processed
+        let projected_group_index = projected_groups
+            .iter()
+            .position(|g| g.id == group_debts_to_pay.group_id);
+        let mut group = match projected_group_index {
+            Some(index) => projected_groups.remove(index),
+            None => check_member_group_membership(instance, giver, group_debts_to_pay.group_id)?,
+        };
+
+        for taker in group_debts_to_pay.takers {
+            let mut payment = SettleUpPayment {
+                group_id: group.id,
+                taker: taker.member_address,
+                amount: taker.value,
+                status: PaymentStatus::Skipped,
+                error: None,
+            };
+
+            if has_failed {
+                payments.push(payment);
+                continue;
+            }
+
+            if let Err(error) = validate_debt_to_pay(&group, giver, &taker) {
+                payment.error = Some(error);
+                payments.push(payment);
+                continue;
+            }
+
+            if taker.value > remaining_balance {
+                has_failed = true;
+                payment.status = PaymentStatus::Failed;
+                payment.error = Some(ContractError::TransferInsufficientBalance);
+            } else if taker.value > remaining_allowance {
+                has_failed = true;
+                payment.status = PaymentStatus::Failed;
+                payment.error = Some(ContractError::TransferInsufficientAllowance);
+            } else {
+                apply_group_payment(&mut group, giver, taker.member_address, taker.value)?;
+                remaining_balance = remaining_balance.saturating_sub(taker.value);
+                remaining_allowance = remaining_allowance.saturating_sub(taker.value);
+                payment.status = PaymentStatus::Settled;
+            }
+
+            payments.push(payment);
+        }
+
+        projected_groups.push(group);
+    }
+
+    Ok(SettleUpSimulation {
+        result: payments
+            .iter()
+            .all(|payment| payment.status == PaymentStatus::Settled),
+        payments,
+        balance,
+        allowance,
+        projected_groups,
+    })
+}
+
+/// Checks if the specified group exists and if the caller is one of its admins.
+pub fn check_group_admin(instance: &Splitmate, group_id: u128) -> Result<Group, ContractError> {
+    let group = check_group_membership(instance, group_id)?;
+
+    if !group.is_admin(instance.env().caller()) {
+        return Err(ContractError::CallerIsNotGroupAdmin);
+    }
+
+    Ok(group)
+}
+
 pub fn get_group_by_id(instance: &Splitmate, group_id: u128) -> Result<Group, ContractError> {
     match instance.groups.get(group_id) {
         Some(group) => Ok(group),
@@ -164,12 +705,22 @@ pub fn get_group_by_id(instance: &Splitmate, group_id: u128) -> Result<Group, Co
 }
 
 /// Gets a group debts distribution.
-/// Using two groups (givers & takers), calculates the transfers to be done between members.
+/// Checks if the specified group exists and if the caller is inside of it.
 pub fn get_group_distribution(
     instance: &Splitmate,
     group_id: u128,
 ) -> Result<Vec<GroupMemberDistribution>, ContractError> {
     let group = check_group_membership(instance, group_id)?;
+    build_group_distribution(instance, &group)
+}
+
+/// Builds a group debts distribution.
+/// Using two groups (givers & takers), calculates the transfers to be done between members.
+/// The accepted debt assignments are paid directly to the new creditors first.
+pub fn build_group_distribution(
+    instance: &Splitmate,
+    group: &Group,
+) -> Result<Vec<GroupMemberDistribution>, ContractError> {
     let mut group_distribution = Vec::<GroupMemberDistribution>::new();
 
     let givers: Vec<GroupMember> = group
@@ -191,29 +742,110 @@ pub fn get_group_distribution(
         .collect();
     takers.sort_by(|a, b| a.debt_value.cmp(&b.debt_value));
 
-    for giver in givers {
+    let debt_assignments: Vec<DebtAssignment> = instance
+        .group_debt_assignments
+        .get(group.id)
+        .unwrap_or(Vec::<DebtAssignment>::new())
+        .into_iter()
+        .filter(|a| a.is_accepted())
+        .collect();
+
+    // Route the assigned debts before distributing the remaining ones
+    let mut pending_debts = Vec::<u128>::new();
+    for giver in givers.iter() {
         let mut distribution_member = GroupMemberDistribution {
             member_account: giver.address,
             total_debt: giver.debt_value,
             transfers: Vec::<GroupMemberDistributionTransfer>::new(),
         };
 
-        let mut pending_debt = giver.debt_value as u128;
-        while pending_debt > 0 {
-            let debt_transfer = process_giver_debt(pending_debt, &mut takers);
+        let mut pending_debt = giver.debt_value.unsigned_abs();
+        for debt_assignment in debt_assignments
+            .iter()
+            .filter(|a| a.debtor == giver.address)
+        {
+            let taker_index = match takers
+                .iter()
+                .position(|t| t.address == debt_assignment.new_creditor)
+            {
+                Some(index) => index,
+                None => continue,
+            };
 
-            distribution_member.transfers.push(debt_transfer.clone());
+            let value = pending_debt
+                .min(debt_assignment.amount)
+                .min(takers[taker_index].debt_value.unsigned_abs());
+            if value == 0 {
+                continue;
+            }
+
+            takers[taker_index].debt_value = takers[taker_index]
+                .debt_value
+                .checked_add(to_balance(value)?)
+                .ok_or(ContractError::Overflow)?;
+            if takers[taker_index].debt_value == 0 {
+                takers.remove(taker_index);
+            }
 
-            pending_debt = pending_debt.checked_sub(debt_transfer.value).unwrap();
+            distribution_member
+                .transfers
+                .push(GroupMemberDistributionTransfer {
+                    member_account: debt_assignment.new_creditor,
+                    value,
+                });
+            pending_debt = pending_debt
+                .checked_sub(value)
+                .ok_or(ContractError::Overflow)?;
         }
 
         group_distribution.push(distribution_member);
+        pending_debts.push(pending_debt);
+    }
+
+    for (distribution_member, mut pending_debt) in group_distribution.iter_mut().zip(pending_debts)
+    {
+        while pending_debt > 0 && takers.len() > 0 {
+            let debt_transfer = process_giver_debt(pending_debt, &mut takers)?;
+
+            distribution_member.transfers.push(debt_transfer.clone());
+
+            pending_debt = pending_debt
+                .checked_sub(debt_transfer.value)
+                .ok_or(ContractError::Overflow)?;
+        }
     }
 
     Ok(group_distribution)
 }
 
+/// Gets the amount a debtor has to transfer to a creditor in the group debts distribution.
+pub fn get_planned_debt(
+    instance: &Splitmate,
+    group: &Group,
+    debtor: AccountId,
+    creditor: AccountId,
+) -> Result<u128, ContractError> {
+    let mut planned_debt: u128 = 0;
+    for member_distribution in build_group_distribution(instance, group)?
+        .into_iter()
+        .filter(|d| d.member_account == debtor)
+    {
+        for transfer in member_distribution
+            .transfers
+            .iter()
+            .filter(|t| t.member_account == creditor)
+        {
+            planned_debt = planned_debt
+                .checked_add(transfer.value)
+                .ok_or(ContractError::Overflow)?;
+        }
+    }
+
+    Ok(planned_debt)
+}
+
 /// Gets a member debts distribution for all his groups.
+/// Includes the member balance, who the member owes and who owes the member.
 pub fn get_member_group_distributions(
     instance: &Splitmate,
     member_address: AccountId,
@@ -226,14 +858,36 @@ pub fn get_member_group_distributions(
     let mut caller_distributions = Vec::<GroupDistributionByMember>::new();
 
     for group_id in member_groups {
-        let group_distribution = get_group_distribution(instance, group_id)?;
-        let group_distribution_by_caller = group_distribution
-            .iter()
-            .find(|gmd| gmd.member_account == member_address)
-            .unwrap();
+        let group = get_group_by_id(instance, group_id)?;
+        let balance = match group.members.iter().find(|m| m.address == member_address) {
+            Some(member) => member.debt_value,
+            None => 0,
+        };
+
+        let mut debts = Vec::<GroupMemberDistributionTransfer>::new();
+        let mut credits = Vec::<GroupMemberDistributionTransfer>::new();
+
+        for member_distribution in get_group_distribution(instance, group_id)? {
+            if member_distribution.member_account == member_address {
+                debts.extend(member_distribution.transfers);
+                continue;
+            }
+
+            for transfer in member_distribution.transfers {
+                if transfer.member_account == member_address {
+                    credits.push(GroupMemberDistributionTransfer {
+                        member_account: member_distribution.member_account,
+                        value: transfer.value,
+                    });
+                }
+            }
+        }
+
         caller_distributions.push(GroupDistributionByMember {
             group_id,
-            member_distribution: group_distribution_by_caller.clone(),
+            balance,
+            debts,
+            credits,
         });
     }
 
@@ -252,7 +906,7 @@ pub fn get_member_groups(
     }
 
     for group_id in group_ids.unwrap() {
-        member_groups.push(instance.groups.get(group_id).unwrap());
+        member_groups.push(get_group_by_id(instance, group_id)?);
     }
 
     Ok(member_groups)
@@ -270,3 +924,2263 @@ pub fn add_to_member_groups(instance: &mut Splitmate, member_address: AccountId,
         .member_groups
         .insert(member_address, &member_groups);
 }
+
+/// Gets the member pairwise balances netted across all his groups.
+/// Uses each group debts distribution to know who owes whom inside the group.
+pub fn get_member_net_balances(
+    instance: &Splitmate,
+    member_address: AccountId,
+) -> Result<Vec<MemberNetBalance>, ContractError> {
+    let member_groups = instance
+        .member_groups
+        .get(member_address)
+        .unwrap_or(Vec::<u128>::new());
+
+    let mut net_balances = Vec::<MemberNetBalance>::new();
+
+    for group_id in member_groups {
+        let group_distribution = get_group_distribution(instance, group_id)?;
+
+        for member_distribution in group_distribution {
+            for transfer in member_distribution.transfers {
+                // Positive when the member owes the counterpart, negative otherwise
+                let (counterpart, value) = if member_distribution.member_account == member_address {
+                    (transfer.member_account, to_balance(transfer.value)?)
+                } else if transfer.member_account == member_address {
+                    (
+                        member_distribution.member_account,
+                        -to_balance(transfer.value)?,
+                    )
+                } else {
+                    continue;
+                };
+
+                add_to_net_balance(&mut net_balances, counterpart, group_id, value)?;
+            }
+        }
+    }
+
+    Ok(net_balances)
+}
+
+fn add_to_net_balance(
+    net_balances: &mut Vec<MemberNetBalance>,
+    counterpart: AccountId,
+    group_id: u128,
+    value: i128,
+) -> BaseResult {
+    let net_balance_index = net_balances
+        .iter()
+        .position(|b| b.member_account == counterpart);
+    let net_balance_index = match net_balance_index {
+        Some(index) => index,
+        None => {
+            net_balances.push(MemberNetBalance {
+                member_account: counterpart,
+                net_value: 0,
+                balances_by_group: Vec::<GroupPairBalance>::new(),
+            });
+            net_balances.len() - 1
+        }
+    };
+    let net_balance = &mut net_balances[net_balance_index];
+
+    net_balance.net_value = net_balance
+        .net_value
+        .checked_add(value)
+        .ok_or(ContractError::Overflow)?;
+
+    match net_balance
+        .balances_by_group
+        .iter_mut()
+        .find(|b| b.group_id == group_id)
+    {
+        Some(group_balance) => {
+            group_balance.value = group_balance
+                .value
+                .checked_add(value)
+                .ok_or(ContractError::Overflow)?;
+        }
+        None => net_balance
+            .balances_by_group
+            .push(GroupPairBalance { group_id, value }),
+    }
+
+    Ok(())
+}
+
+pub fn add_to_group_settlements(
+    instance: &mut Splitmate,
+    group_id: u128,
+    giver: AccountId,
+    taker: AccountId,
+    amount: u128,
+    kind: SettlementKind,
+) {
+    let mut group_settlements = instance
+        .group_settlements
+        .get(group_id)
+        .unwrap_or(Vec::<Settlement>::new());
+
+    let settlement = Settlement {
+        group_id,
+        kind,
+        giver,
+        taker,
+        amount,
+        token_address: instance.token_address,
+        block_number: instance.env().block_number(),
+        timestamp: instance.env().block_timestamp(),
+    };
+
+    // The debt assignment from the giver to the taker is (partially) paid
+    if settlement.affects_balances() {
+        let mut group_debt_assignments = instance
+            .group_debt_assignments
+            .get(group_id)
+            .unwrap_or(Vec::<DebtAssignment>::new());
+        let mut pending_amount = amount;
+        for debt_assignment in group_debt_assignments
+            .iter_mut()
+            .filter(|a| a.is_accepted() && a.debtor == giver && a.new_creditor == taker)
+        {
+            let paid_amount = pending_amount.min(debt_assignment.amount);
+            debt_assignment.amount -= paid_amount;
+            pending_amount -= paid_amount;
+        }
+        if pending_amount != amount {
+            group_debt_assignments.retain(|a| !a.is_accepted() || a.amount > 0);
+            instance
+                .group_debt_assignments
+                .insert(group_id, &group_debt_assignments);
+        }
+    }
+
+    group_settlements.push(settlement);
+    instance
+        .group_settlements
+        .insert(group_id, &group_settlements);
+}
+
+/// Transfers ERC20 tokens from the giver to the taker.
+/// Adds the payment to the Mapping Group ID -> Group settlements.
+pub fn pay_group_settlement(
+    instance: &mut Splitmate,
+    group_id: u128,
+    giver: AccountId,
+    taker: AccountId,
+    amount: u128,
+    kind: SettlementKind,
+) -> BaseResult {
+    PSP22Ref::transfer_from(
+        &mut instance.token_address,
+        giver,
+        taker,
+        amount,
+        Vec::new(),
+    )
+    .map_err(ContractError::from)?;
+    add_to_group_settlements(instance, group_id, giver, taker, amount, kind);
+
+    Ok(())
+}
+
+/// Recomputes the group member balances replaying all the stored expenses and settlements.
+pub fn replay_group_balances(instance: &Splitmate, group: &Group) -> Result<Group, ContractError> {
+    let mut replayed_group = group.clone();
+    for member in replayed_group.members.iter_mut() {
+        member.debt_value = 0;
+    }
+
+    let group_expenses = instance
+        .group_expenses
+        .get(group.id)
+        .unwrap_or(Vec::<Expense>::new());
+    for expense in group_expenses.iter().filter(|e| e.affects_balances()) {
+        process_expense_debts(&mut replayed_group, expense)?;
+    }
+
+    let group_settlements = instance
+        .group_settlements
+        .get(group.id)
+        .unwrap_or(Vec::<Settlement>::new());
+    for settlement in group_settlements.iter().filter(|s| s.affects_balances()) {
+        apply_group_payment(
+            &mut replayed_group,
+            settlement.giver,
+            settlement.taker,
+            settlement.amount,
+        )?;
+    }
+
+    Ok(replayed_group)
+}
+
+/// Recomputes the group spending statistics replaying all the stored expenses not deleted.
+pub fn replay_group_stats(
+    instance: &Splitmate,
+    group: &Group,
+) -> Result<GroupStats, ContractError> {
+    let mut replayed_stats = GroupStats::new(group.id);
+
+    let group_expenses = instance
+        .group_expenses
+        .get(group.id)
+        .unwrap_or(Vec::<Expense>::new());
+    for expense in group_expenses
+        .iter()
+        .filter(|e| e.status != ExpenseStatus::DELETED)
+    {
+        update_group_stats(&mut replayed_stats, expense)?;
+    }
+
+    Ok(replayed_stats)
+}
+
+/// Compares the stored group member balances with the replayed ones.
+pub fn audit_group_balances(
+    instance: &Splitmate,
+    group: &Group,
+) -> Result<GroupAudit, ContractError> {
+    let replayed_group = replay_group_balances(instance, group)?;
+    let mut discrepancies = Vec::<MemberBalanceDiscrepancy>::new();
+
+    for member in group.members.iter() {
+        let replayed_balance = replayed_group
+            .members
+            .iter()
+            .find(|m| m.address == member.address)
+            .ok_or(ContractError::MemberNotFound)?
+            .debt_value;
+
+        if replayed_balance != member.debt_value {
+            discrepancies.push(MemberBalanceDiscrepancy {
+                member_account: member.address,
+                stored_balance: member.debt_value,
+                replayed_balance,
+            });
+        }
+    }
+
+    Ok(GroupAudit {
+        group_id: group.id,
+        is_consistent: discrepancies.len() == 0,
+        discrepancies,
+    })
+}
+
+/// Gets the placeholder address of a group guest member.
+pub fn get_guest_address(group_id: u128, guest_id: u32) -> AccountId {
+    let mut output = <Blake2x256 as HashOutput>::Type::default();
+    ink::env::hash_encoded::<Blake2x256, _>(
+        &(GUEST_ADDRESS_PREFIX, group_id, guest_id),
+        &mut output,
+    );
+
+    AccountId::from(output)
+}
+
+/// Checks if a guest claim was signed with the invite key.
+/// The signed message is the Blake2x256 hash of the SCALE encoded (contract address, group ID, guest ID, claimant),
+/// so the signature cannot be used by another account.
+/// The invite account is the Blake2x256 hash of the recovered compressed public key.
+pub fn verify_guest_claim(
+    instance: &Splitmate,
+    group_id: u128,
+    guest_id: u32,
+    claimant: AccountId,
+    invite_account: AccountId,
+    signature: &[u8; 65],
+) -> BaseResult {
+    let mut message_hash = <Blake2x256 as HashOutput>::Type::default();
+    ink::env::hash_encoded::<Blake2x256, _>(
+        &(instance.env().account_id(), group_id, guest_id, claimant),
+        &mut message_hash,
+    );
+
+    let mut public_key = [0u8; 33];
+    ink::env::ecdsa_recover(signature, &message_hash, &mut public_key)
+        .map_err(|_| ContractError::InvalidInviteSignature)?;
+
+    let mut signer = <Blake2x256 as HashOutput>::Type::default();
+    ink::env::hash_bytes::<Blake2x256>(&public_key, &mut signer);
+
+    if AccountId::from(signer) != invite_account {
+        return Err(ContractError::InvalidInviteSignature);
+    }
+
+    Ok(())
+}
+
+/// Replaces a member address in the group members, admins, expenses and settlements.
+/// The group is updated in place and must be stored by the caller.
+pub fn replace_member_address(
+    instance: &mut Splitmate,
+    group: &mut Group,
+    old_address: AccountId,
+    new_address: AccountId,
+) -> BaseResult {
+    let member = group
+        .members
+        .iter_mut()
+        .find(|m| m.address == old_address)
+        .ok_or(ContractError::MemberNotFound)?;
+    member.address = new_address;
+
+    for admin in group.admins.iter_mut() {
+        if *admin == old_address {
+            *admin = new_address;
+        }
+    }
+
+    let mut group_expenses = instance
+        .group_expenses
+        .get(group.id)
+        .unwrap_or(Vec::<Expense>::new());
+    for expense in group_expenses.iter_mut() {
+        if expense.created_by == old_address {
+            expense.created_by = new_address;
+        }
+        for expense_member in expense.members.iter_mut() {
+            if expense_member.address == old_address {
+                expense_member.address = new_address;
+            }
+        }
+        for item in expense.items.iter_mut() {
+            for distribution_by_member in item.distribution.distribution_by_members.iter_mut() {
+                if distribution_by_member.member_address == old_address {
+                    distribution_by_member.member_address = new_address;
+                }
+            }
+        }
+    }
+    instance.group_expenses.insert(group.id, &group_expenses);
+
+    for expense in group_expenses.iter() {
+        if let Some(mut expense_dispute) = instance.expense_disputes.get((group.id, expense.id)) {
+            if expense_dispute.disputed_by == old_address {
+                expense_dispute.disputed_by = new_address;
+            }
+            for vote in expense_dispute.votes.iter_mut() {
+                if vote.voter == old_address {
+                    vote.voter = new_address;
+                }
+                if let DisputeResolution::Amend(expense_input) = &mut vote.resolution {
+                    replace_expense_input_address(expense_input, old_address, new_address);
+                }
+            }
+            instance
+                .expense_disputes
+                .insert((group.id, expense.id), &expense_dispute);
+        }
+    }
+
+    let mut group_recurring_expenses = instance
+        .group_recurring_expenses
+        .get(group.id)
+        .unwrap_or(Vec::<RecurringExpense>::new());
+    for recurring_expense in group_recurring_expenses.iter_mut() {
+        if recurring_expense.created_by == old_address {
+            recurring_expense.created_by = new_address;
+        }
+        replace_expense_input_address(&mut recurring_expense.expense, old_address, new_address);
+    }
+    instance
+        .group_recurring_expenses
+        .insert(group.id, &group_recurring_expenses);
+
+    if let Some(mut group_debt_assignments) = instance.group_debt_assignments.get(group.id) {
+        for debt_assignment in group_debt_assignments.iter_mut() {
+            if debt_assignment.assigned_by == old_address {
+                debt_assignment.assigned_by = new_address;
+            }
+            if debt_assignment.debtor == old_address {
+                debt_assignment.debtor = new_address;
+            }
+            if debt_assignment.new_creditor == old_address {
+                debt_assignment.new_creditor = new_address;
+            }
+        }
+        instance
+            .group_debt_assignments
+            .insert(group.id, &group_debt_assignments);
+    }
+
+    if let Some(threshold) = instance.auto_settle_thresholds.get((group.id, old_address)) {
+        instance
+            .auto_settle_thresholds
+            .remove((group.id, old_address));
+        instance
+            .auto_settle_thresholds
+            .insert((group.id, new_address), &threshold);
+    }
+
+    if let Some(mut group_loans) = instance.group_loans.get(group.id) {
+        for loan in group_loans.iter_mut() {
+            if loan.lender == old_address {
+                loan.lender = new_address;
+            }
+            if loan.borrower == old_address {
+                loan.borrower = new_address;
+            }
+        }
+        instance.group_loans.insert(group.id, &group_loans);
+    }
+
+    if let Some(mut group_pot) = instance.group_pots.get(group.id) {
+        for contribution in group_pot.contributions.iter_mut() {
+            if contribution.address == old_address {
+                contribution.address = new_address;
+            }
+        }
+        for payer in group_pot.approved_payers.iter_mut() {
+            if *payer == old_address {
+                *payer = new_address;
+            }
+        }
+        instance.group_pots.insert(group.id, &group_pot);
+    }
+
+    if let Some(mut group_stats) = instance.group_stats.get(group.id) {
+        for member_stats in group_stats.members.iter_mut() {
+            if member_stats.address == old_address {
+                member_stats.address = new_address;
+            }
+        }
+        instance.group_stats.insert(group.id, &group_stats);
+    }
+
+    let mut group_settlements = instance
+        .group_settlements
+        .get(group.id)
+        .unwrap_or(Vec::<Settlement>::new());
+    for settlement in group_settlements.iter_mut() {
+        if settlement.giver == old_address {
+            settlement.giver = new_address;
+        }
+        if settlement.taker == old_address {
+            settlement.taker = new_address;
+        }
+    }
+    instance
+        .group_settlements
+        .insert(group.id, &group_settlements);
+
+    Ok(())
+}
+
+pub fn remove_from_member_groups(
+    instance: &mut Splitmate,
+    member_address: AccountId,
+    group_id: u128,
+) {
+    let mut member_groups = instance
+        .member_groups
+        .get(member_address)
+        .unwrap_or(Vec::<u128>::new());
+
+    member_groups.retain(|member_group_id| *member_group_id != group_id);
+
+    if member_groups.len() == 0 {
+        instance.member_groups.remove(member_address);
+    } else {
+        instance
+            .member_groups
+            .insert(member_address, &member_groups);
+    }
+}
+
+/// Moves a member of a group to a new address.
+/// Checks if the new address is not already in the group.
+/// Replaces the old address in the group members and history.
+/// Moves the group ID in the Mapping Member -> Group IDs.
+/// Drops the group from the old address delegations and its pending recoveries.
+/// Carries the old address signer nonce over to the new address.
+pub fn migrate_member_group(
+    instance: &mut Splitmate,
+    group_id: u128,
+    old_address: AccountId,
+    new_address: AccountId,
+) -> BaseResult {
+    let mut group = get_group_by_id(instance, group_id)?;
+
+    if group.members.iter().any(|m| m.address == new_address) {
+        return Err(ContractError::MemberIsAlreadyInTheGroup);
+    }
+
+    replace_member_address(instance, &mut group, old_address, new_address)?;
+    instance.groups.insert(group_id, &group);
+
+    remove_from_member_groups(instance, old_address, group_id);
+    add_to_member_groups(instance, new_address, group_id);
+
+    remove_member_group_delegations(instance, old_address, group_id);
+    instance.account_recoveries.remove((group_id, old_address));
+
+    let old_nonce = instance.signer_nonces.get(old_address).unwrap_or(0);
+    if old_nonce > instance.signer_nonces.get(new_address).unwrap_or(0) {
+        instance.signer_nonces.insert(new_address, &old_nonce);
+    }
+
+    Ok(())
+}
+
+/// Adds a delegation key to the Mapping Member -> Delegations of its principal and delegate.
+pub fn add_to_member_delegations(
+    instance: &mut Splitmate,
+    principal: AccountId,
+    delegate: AccountId,
+) {
+    for member_address in [principal, delegate] {
+        let mut member_delegations = instance
+            .member_delegations
+            .get(member_address)
+            .unwrap_or(Vec::<(AccountId, AccountId)>::new());
+
+        if !member_delegations.contains(&(principal, delegate)) {
+            member_delegations.push((principal, delegate));
+            instance
+                .member_delegations
+                .insert(member_address, &member_delegations);
+        }
+    }
+}
+
+/// Removes a delegation key from the Mapping Member -> Delegations of its principal and delegate.
+pub fn remove_from_member_delegations(
+    instance: &mut Splitmate,
+    principal: AccountId,
+    delegate: AccountId,
+) {
+    for member_address in [principal, delegate] {
+        let mut member_delegations = instance
+            .member_delegations
+            .get(member_address)
+            .unwrap_or(Vec::<(AccountId, AccountId)>::new());
+
+        member_delegations.retain(|key| *key != (principal, delegate));
+
+        if member_delegations.len() == 0 {
+            instance.member_delegations.remove(member_address);
+        } else {
+            instance
+                .member_delegations
+                .insert(member_address, &member_delegations);
+        }
+    }
+}
+
+/// Removes a group from the delegations where the member is the principal or the delegate.
+/// Revokes the delegations left without groups.
+fn remove_member_group_delegations(
+    instance: &mut Splitmate,
+    member_address: AccountId,
+    group_id: u128,
+) {
+    let member_delegations = instance
+        .member_delegations
+        .get(member_address)
+        .unwrap_or(Vec::<(AccountId, AccountId)>::new());
+
+    for key in member_delegations {
+        if let Some(mut delegation) = instance.delegations.get(key) {
+            delegation.group_ids.retain(|id| *id != group_id);
+
+            if delegation.group_ids.len() == 0 {
+                instance.delegations.remove(key);
+                remove_from_member_delegations(instance, key.0, key.1);
+            } else {
+                instance.delegations.insert(key, &delegation);
+            }
+        }
+    }
+}
+
+/// Adds an expense to a specific group on behalf of a member.
+pub fn add_member_expense(
+    instance: &mut Splitmate,
+    member_address: AccountId,
+    expense_to_add: ExpenseInput,
+) -> BaseResult {
+    add_member_expenses(instance, member_address, [expense_to_add].to_vec())?;
+
+    Ok(())
+}
+
+/// Adds several expenses to their groups on behalf of a member.
+/// Checks if the member is in every specified group.
+/// Validates all the expenses and updates the member balances/debts before storing anything.
+/// Stores each affected group and its expenses once.
+/// Updates the Group Expense ID incremental.
+pub fn add_member_expenses(
+    instance: &mut Splitmate,
+    member_address: AccountId,
+    expenses_to_add: Vec<ExpenseInput>,
+) -> Result<Vec<AddedExpense>, ContractError> {
+    let expenses = expenses_to_add
+        .into_iter()
+        .map(|expense_to_add| Expense::new(0, member_address, expense_to_add))
+        .collect();
+
+    add_group_expenses(instance, member_address, expenses)
+}
+
+/// Adds several built expenses to their groups on behalf of a member.
+/// Checks if the member is in every specified group.
+/// Validates all the expenses and updates the member balances/debts before storing anything.
+/// Assigns the expense IDs using the Group Expense ID incremental.
+/// Stores each affected group and its expenses once.
+/// Checks the auto-settle thresholds of the expense members once all the expenses are added.
+pub fn add_group_expenses(
+    instance: &mut Splitmate,
+    member_address: AccountId,
+    expenses: Vec<Expense>,
+) -> Result<Vec<AddedExpense>, ContractError> {
+    let added_expenses = store_group_expenses(instance, member_address, expenses)?;
+    check_auto_settle_thresholds(instance, &added_expenses)?;
+
+    Ok(added_expenses
+        .iter()
+        .map(|expense| AddedExpense {
+            group_id: expense.group_id,
+            expense_id: expense.id,
+        })
+        .collect())
+}
+
+/// Adds several built expenses to their groups, as specified in add_group_expenses, without checking the auto-settle thresholds.
+/// Informs the added expenses.
+fn store_group_expenses(
+    instance: &mut Splitmate,
+    member_address: AccountId,
+    expenses: Vec<Expense>,
+) -> Result<Vec<Expense>, ContractError> {
+    let mut groups = Vec::<Group>::new();
+    let mut groups_expenses = Vec::<Vec<Expense>>::new();
+    let mut groups_stats = Vec::<GroupStats>::new();
+    let mut added_expenses = Vec::<Expense>::new();
+
+    for mut expense in expenses {
+        let group_index = match groups.iter().position(|g| g.id == expense.group_id) {
+            Some(index) => index,
+            None => {
+                let group =
+                    check_member_group_membership(instance, member_address, expense.group_id)?;
+                let group_expenses = instance
+                    .group_expenses
+                    .get(group.id)
+                    .unwrap_or(Vec::<Expense>::new());
+                let group_stats = instance
+                    .group_stats
+                    .get(group.id)
+                    .unwrap_or(GroupStats::new(group.id));
+
+                groups.push(group);
+                groups_expenses.push(group_expenses);
+                groups_stats.push(group_stats);
+                groups.len() - 1
+            }
+        };
+        let group = &mut groups[group_index];
+
+        expense.id = group.next_expense_id;
+        expense.created_at = instance.env().block_timestamp();
+        if expense.settle_by.is_none() {
+            expense.settle_by = match group.settle_by_term {
+                Some(term) => Some(
+                    expense
+                        .created_at
+                        .checked_add(term)
+                        .ok_or(ContractError::Overflow)?,
+                ),
+                None => None,
+            };
+        }
+
+        expense.validate()?;
+        process_expense_debts(group, &expense)?;
+        apply_group_budget(instance, group, &groups_stats[group_index], &expense)?;
+        update_group_stats(&mut groups_stats[group_index], &expense)?;
+
+        added_expenses.push(expense.clone());
+        groups_expenses[group_index].push(expense);
+
+        group.next_expense_id = group
+            .next_expense_id
+            .checked_add(1)
+            .ok_or(ContractError::Overflow)?;
+    }
+
+    for ((group, group_expenses), group_stats) in groups
+        .iter()
+        .zip(groups_expenses.iter())
+        .zip(groups_stats.iter())
+    {
+        instance.group_expenses.insert(group.id, group_expenses);
+        instance.group_stats.insert(group.id, group_stats);
+        instance.groups.insert(group.id, group);
+    }
+
+    Ok(added_expenses)
+}
+
+/// Settles up selected debts for specific groups on behalf of a member.
+/// Validates each debt against the current group balances.
+/// Transfers ERC20 tokens from the member for each debt.
+/// Stops transferring after the first failed payment, skipping the remaining ones.
+/// Updates the group debts.
+/// Adds each paid debt to the Mapping Group ID -> Group settlements.
+/// Informs the status of every requested payment.
+pub fn settle_member_debts(
+    instance: &mut Splitmate,
+    giver: AccountId,
+    debts_to_pay: Vec<GroupDebtsToPay>,
+) -> Result<SettleUpResult, ContractError> {
+    let mut payments = Vec::<SettleUpPayment>::new();
+    let mut has_failed = false;
+
+    for group_debts_to_pay in debts_to_pay {
+        let mut group =
+            check_member_group_membership(instance, giver, group_debts_to_pay.group_id)?;
+
+        for taker in group_debts_to_pay.takers {
+            let mut payment = SettleUpPayment {
+                group_id: group.id,
+                taker: taker.member_address,
+                amount: taker.value,
+                status: PaymentStatus::Skipped,
+                error: None,
+            };
+
+            if has_failed {
+                payments.push(payment);
+                continue;
+            }
+
+            if let Err(error) = validate_debt_to_pay(&group, giver, &taker) {
+                payment.error = Some(error);
+                payments.push(payment);
+                continue;
+            }
+
+            match pay_group_settlement(
+                instance,
+                group.id,
+                giver,
+                taker.member_address,
+                taker.value,
+                SettlementKind::PAYMENT,
+            ) {
+                Ok(()) => {
+                    apply_group_payment(&mut group, giver, taker.member_address, taker.value)?;
+                    payment.status = PaymentStatus::Settled;
+                }
+                Err(error) => {
+                    has_failed = true;
+                    payment.status = PaymentStatus::Failed;
+                    payment.error = Some(error);
+                }
+            }
+
+            payments.push(payment);
+        }
+
+        instance.groups.insert(group.id, &group);
+    }
+
+    Ok(SettleUpResult {
+        result: payments
+            .iter()
+            .all(|payment| payment.status == PaymentStatus::Settled),
+        payments,
+    })
+}
+
+/// Repays (part of) a loan on behalf of its borrower, paying its instalments in order.
+/// Transfers ERC20 tokens from the borrower to the lender.
+/// Adds the repayment to the Mapping Group ID -> Group settlements, apart from the group balances.
+/// Informs the status of the payment.
+pub fn repay_member_loan(
+    instance: &mut Splitmate,
+    borrower: AccountId,
+    group_id: u128,
+    loan_id: u32,
+    amount: u128,
+) -> Result<SettleUpResult, ContractError> {
+    check_member_group_membership(instance, borrower, group_id)?;
+
+    let mut group_loans = instance
+        .group_loans
+        .get(group_id)
+        .unwrap_or(Vec::<Loan>::new());
+    let loan = group_loans
+        .iter_mut()
+        .find(|l| l.id == loan_id)
+        .ok_or(ContractError::LoanDoesNotExist)?;
+    if loan.borrower != borrower {
+        return Err(ContractError::CallerIsNotTheBorrower);
+    }
+
+    loan.repay(amount)?;
+    let lender = loan.lender;
+
+    let mut payment = SettleUpPayment {
+        group_id,
+        taker: lender,
+        amount,
+        status: PaymentStatus::Settled,
+        error: None,
+    };
+
+    match pay_group_settlement(
+        instance,
+        group_id,
+        borrower,
+        lender,
+        amount,
+        SettlementKind::LOAN_REPAYMENT { loan_id },
+    ) {
+        Ok(()) => {
+            instance.group_loans.insert(group_id, &group_loans);
+            instance.env().emit_event(LoanRepaid {
+                group_id,
+                loan_id,
+                borrower,
+                lender,
+                amount,
+            });
+        }
+        Err(error) => {
+            payment.status = PaymentStatus::Failed;
+            payment.error = Some(error);
+        }
+    }
+
+    Ok(SettleUpResult {
+        result: payment.status == PaymentStatus::Settled,
+        payments: [payment].to_vec(),
+    })
+}
+
+/// Checks if the caller is a valid delegate of the principal for the action in the group.
+pub fn check_delegation(
+    instance: &Splitmate,
+    principal: AccountId,
+    group_id: u128,
+    action: DelegatedAction,
+) -> BaseResult {
+    let delegation = instance
+        .delegations
+        .get((principal, instance.env().caller()))
+        .ok_or(ContractError::DelegationDoesNotExist)?;
+
+    if delegation.is_expired(instance.env().block_timestamp()) {
+        return Err(ContractError::DelegationIsExpired);
+    }
+
+    if !delegation.allows(group_id, &action) {
+        return Err(ContractError::DelegationDoesNotAllowTheAction);
+    }
+
+    Ok(())
+}
+
+/// Recovers the member who signed an expense submission with ECDSA.
+/// The signed message is the Blake2x256 hash of the SCALE encoded (contract address, expense, nonce).
+/// The member address is the Blake2x256 hash of the recovered compressed public key.
+pub fn recover_expense_signer(
+    instance: &Splitmate,
+    expense_to_add: &ExpenseInput,
+    nonce: u64,
+    signature: &[u8; 65],
+) -> Result<AccountId, ContractError> {
+    let mut message_hash = <Blake2x256 as HashOutput>::Type::default();
+    ink::env::hash_encoded::<Blake2x256, _>(
+        &(instance.env().account_id(), expense_to_add, nonce),
+        &mut message_hash,
+    );
+
+    let mut public_key = [0u8; 33];
+    ink::env::ecdsa_recover(signature, &message_hash, &mut public_key)
+        .map_err(|_| ContractError::InvalidSignature)?;
+
+    let mut signer = <Blake2x256 as HashOutput>::Type::default();
+    ink::env::hash_bytes::<Blake2x256>(&public_key, &mut signer);
+
+    Ok(AccountId::from(signer))
+}
+
+fn replace_expense_input_address(
+    expense_input: &mut ExpenseInput,
+    old_address: AccountId,
+    new_address: AccountId,
+) {
+    if expense_input.payer_address == old_address {
+        expense_input.payer_address = new_address;
+    }
+    for distribution_by_member in expense_input
+        .distribution
+        .distribution_by_members
+        .iter_mut()
+    {
+        if distribution_by_member.member_address == old_address {
+            distribution_by_member.member_address = new_address;
+        }
+    }
+}
+
+/// Gets the current moment in the specified recurrence unit.
+pub fn get_recurrence_now(instance: &Splitmate, unit: &RecurrenceUnit) -> u64 {
+    match unit {
+        RecurrenceUnit::BLOCKS => instance.env().block_number() as u64,
+        RecurrenceUnit::TIMESTAMP => instance.env().block_timestamp(),
+    }
+}
+
+/// Creates the due expenses of all the group recurring expenses.
+/// Each expense is added on behalf of the recurring expense creator.
+/// Materializes a limited number of occurrences by recurring expense in each call.
+/// A failed occurrence is flagged and skipped, so it does not block the next ones nor the other recurring expenses.
+/// Removes the recurring expenses that have ended.
+/// Checks the auto-settle thresholds of the expense members once all the expenses are added.
+pub fn materialize_group_recurring_expenses(
+    instance: &mut Splitmate,
+    group_id: u128,
+) -> Result<Vec<AddedExpense>, ContractError> {
+    get_group_by_id(instance, group_id)?;
+
+    let group_recurring_expenses = instance
+        .group_recurring_expenses
+        .get(group_id)
+        .unwrap_or(Vec::<RecurringExpense>::new());
+
+    let mut added_expenses = Vec::<Expense>::new();
+    let mut pending_recurring_expenses = Vec::<RecurringExpense>::new();
+
+    for mut recurring_expense in group_recurring_expenses {
+        let now = get_recurrence_now(instance, &recurring_expense.unit);
+        let mut materialized_occurrences = 0;
+
+        while recurring_expense.is_due(now)
+            && materialized_occurrences < MAX_RECURRING_OCCURRENCES_PER_CALL
+        {
+            let next_occurrence = recurring_expense
+                .next_occurrence
+                .checked_add(recurring_expense.period)
+                .ok_or(ContractError::Overflow)?;
+
+            let expense = Expense::new(
+                0,
+                recurring_expense.created_by,
+                recurring_expense.expense.clone(),
+            );
+            match store_group_expenses(instance, recurring_expense.created_by, [expense].to_vec()) {
+                Ok(added) => added_expenses.extend(added),
+                Err(error) => {
+                    instance.env().emit_event(RecurringExpenseFailed {
+                        group_id,
+                        recurring_expense_id: recurring_expense.id,
+                        occurrence: recurring_expense.next_occurrence,
+                        error,
+                    });
+                }
+            }
+
+            recurring_expense.next_occurrence = next_occurrence;
+            materialized_occurrences += 1;
+        }
+
+        if !recurring_expense.has_ended() {
+            pending_recurring_expenses.push(recurring_expense);
+        }
+    }
+
+    instance
+        .group_recurring_expenses
+        .insert(group_id, &pending_recurring_expenses);
+    check_auto_settle_thresholds(instance, &added_expenses)?;
+
+    Ok(added_expenses
+        .iter()
+        .map(|expense| AddedExpense {
+            group_id: expense.group_id,
+            expense_id: expense.id,
+        })
+        .collect())
+}
+
+/// Splits an amount equally, assigning the remainder to the first parts.
+pub fn split_amount_equally(amount: u128, parts: u128) -> Result<Vec<u128>, ContractError> {
+    let base = amount
+        .checked_div(parts)
+        .ok_or(ContractError::ExpenseItemWithoutMembers)?;
+    let remainder = amount % parts;
+
+    Ok((0..parts)
+        .map(|part| if part < remainder { base + 1 } else { base })
+        .collect())
+}
+
+fn add_to_distribution(
+    distribution_by_members: &mut Vec<DistributionByMemberInput>,
+    member_address: AccountId,
+    value: u128,
+) -> BaseResult {
+    match distribution_by_members
+        .iter_mut()
+        .find(|d| d.member_address == member_address)
+    {
+        Some(distribution_by_member) => {
+            distribution_by_member.value = distribution_by_member
+                .value
+                .checked_add(value)
+                .ok_or(ContractError::Overflow)?;
+        }
+        None => distribution_by_members.push(DistributionByMemberInput {
+            member_address,
+            value,
+        }),
+    }
+
+    Ok(())
+}
+
+/// Splits an amount proportionally to the weights.
+/// The rounding remainder is assigned to the first parts with weight.
+pub fn split_amount_proportionally(
+    amount: u128,
+    weights: &[u128],
+) -> Result<Vec<u128>, ContractError> {
+    let mut total_weight: u128 = 0;
+    for weight in weights.iter() {
+        total_weight = total_weight
+            .checked_add(*weight)
+            .ok_or(ContractError::Overflow)?;
+    }
+
+    if amount == 0 {
+        return Ok(weights.iter().map(|_| 0).collect());
+    }
+    if total_weight == 0 {
+        return Err(ContractError::SplitWithoutWeights);
+    }
+
+    let mut parts = Vec::<u128>::new();
+    let mut distributed: u128 = 0;
+    for weight in weights.iter() {
+        let part = amount.checked_mul(*weight).ok_or(ContractError::Overflow)? / total_weight;
+
+        distributed = distributed
+            .checked_add(part)
+            .ok_or(ContractError::Overflow)?;
+        parts.push(part);
+    }
+
+    let mut remainder = amount
+        .checked_sub(distributed)
+        .ok_or(ContractError::Overflow)?;
+    for (part, weight) in parts.iter_mut().zip(weights.iter()) {
+        if remainder == 0 {
+            break;
+        }
+        if *weight > 0 {
+            *part += 1;
+            remainder -= 1;
+        }
+    }
+
+    Ok(parts)
+}
+
+/// Collapses an itemized expense into an unequal distribution by member.
+/// Each item is distributed between its members (equally or unequally).
+/// The shared lines are distributed proportionally to the member item totals.
+/// The rounding remainders are assigned to the first members.
+pub fn collapse_itemized_expense(
+    itemized_expense: &ItemizedExpenseInput,
+) -> Result<ExpenseInput, ContractError> {
+    if itemized_expense.items.len() == 0 {
+        return Err(ContractError::ExpenseWithoutItems);
+    }
+
+    let mut distribution_by_members = Vec::<DistributionByMemberInput>::new();
+    let mut items_total: u128 = 0;
+
+    for item in itemized_expense.items.iter() {
+        if item.amount == 0 {
+            return Err(ContractError::ExpenseItemAmountIsZero);
+        }
+
+        let item_members = &item.distribution.distribution_by_members;
+        if item_members.len() == 0 {
+            return Err(ContractError::ExpenseItemWithoutMembers);
+        }
+
+        let item_shares = match item.distribution.distribution_type {
+            DistributionType::EQUALLY => {
+                split_amount_equally(item.amount, item_members.len() as u128)?
+            }
+            DistributionType::UNEQUALLY => {
+                let mut item_distributed: u128 = 0;
+                for item_member in item_members.iter() {
+                    item_distributed = item_distributed
+                        .checked_add(item_member.value)
+                        .ok_or(ContractError::Overflow)?;
+                }
+                if item_distributed != item.amount {
+                    return Err(ContractError::ExpenseItemDistributionDoesNotMatchAmount);
+                }
+
+                item_members.iter().map(|m| m.value).collect()
+            }
+        };
+
+        for (item_member, item_share) in item_members.iter().zip(item_shares) {
+            add_to_distribution(
+                &mut distribution_by_members,
+                item_member.member_address,
+                item_share,
+            )?;
+        }
+
+        items_total = items_total
+            .checked_add(item.amount)
+            .ok_or(ContractError::Overflow)?;
+    }
+
+    let mut shared_total: u128 = 0;
+    for shared_line in itemized_expense.shared_lines.iter() {
+        shared_total = shared_total
+            .checked_add(shared_line.amount)
+            .ok_or(ContractError::Overflow)?;
+    }
+
+    // Calculate every member share before updating the member totals
+    let mut shared_distributed: u128 = 0;
+    let mut shared_values = Vec::<u128>::new();
+    for distribution_by_member in distribution_by_members.iter() {
+        let shared_value = shared_total
+            .checked_mul(distribution_by_member.value)
+            .ok_or(ContractError::Overflow)?
+            .checked_div(items_total)
+            .ok_or(ContractError::ExpenseWithoutItems)?;
+
+        shared_distributed = shared_distributed
+            .checked_add(shared_value)
+            .ok_or(ContractError::Overflow)?;
+        shared_values.push(shared_value);
+    }
+
+    let mut shared_remainder = shared_total
+        .checked_sub(shared_distributed)
+        .ok_or(ContractError::Overflow)?;
+    for (distribution_by_member, shared_value) in
+        distribution_by_members.iter_mut().zip(shared_values)
+    {
+        distribution_by_member.value = distribution_by_member
+            .value
+            .checked_add(shared_value)
+            .ok_or(ContractError::Overflow)?;
+
+        if shared_remainder > 0 {
+            distribution_by_member.value = distribution_by_member
+                .value
+                .checked_add(1)
+                .ok_or(ContractError::Overflow)?;
+            shared_remainder -= 1;
+        }
+    }
+
+    // The payer must be in the distribution to register the paid amount
+    add_to_distribution(
+        &mut distribution_by_members,
+        itemized_expense.payer_address,
+        0,
+    )?;
+
+    Ok(ExpenseInput {
+        group_id: itemized_expense.group_id,
+        amount: itemized_expense.amount,
+        payer_address: itemized_expense.payer_address,
+        distribution: DistributionInput {
+            distribution_type: DistributionType::UNEQUALLY,
+            distribution_by_members,
+        },
+        category: itemized_expense.category.clone(),
+        settle_by: itemized_expense.settle_by,
+    })
+}
+
+/// Builds a refund of a group expense.
+/// The refund is received by the original payers, proportionally to the amount they paid.
+/// The refund is credited to the original distribution members, or to the new distribution if specified.
+/// Checks the original expense is active (not disputed, frozen or deleted).
+/// Checks the refunds do not exceed the original expense amount.
+pub fn build_refund_expense(
+    instance: &Splitmate,
+    created_by: AccountId,
+    refund_to_add: RefundInput,
+) -> Result<Expense, ContractError> {
+    let group_expenses = instance
+        .group_expenses
+        .get(refund_to_add.group_id)
+        .unwrap_or(Vec::<Expense>::new());
+
+    let original_expense = group_expenses
+        .iter()
+        .find(|e| e.id == refund_to_add.original_expense_id)
+        .ok_or(ContractError::ExpenseDoesNotExist)?;
+    if original_expense.kind != ExpenseKind::EXPENSE
+        || original_expense.status != ExpenseStatus::ACTIVE
+    {
+        return Err(ContractError::ExpenseCannotBeRefunded);
+    }
+
+    let mut refunded_amount = refund_to_add.amount;
+    for refund in group_expenses
+        .iter()
+        .filter(|e| e.is_refund_of(original_expense.id))
+    {
+        refunded_amount = refunded_amount
+            .checked_add(refund.amount)
+            .ok_or(ContractError::Overflow)?;
+    }
+    if refunded_amount > original_expense.amount {
+        return Err(ContractError::RefundExceedsExpenseAmount);
+    }
+
+    // The original payers receive the refund
+    let paid_amounts: Vec<u128> = original_expense.members.iter().map(|m| m.paid).collect();
+    if paid_amounts.iter().all(|paid| *paid == 0) {
+        return Err(ContractError::ExpenseWithoutPayers);
+    }
+    let received_amounts = split_amount_proportionally(refund_to_add.amount, &paid_amounts)?;
+
+    let mut members: Vec<ExpenseMember> = original_expense
+        .members
+        .iter()
+        .zip(received_amounts)
+        .map(|(original_member, received_amount)| ExpenseMember {
+            address: original_member.address,
+            paid: received_amount,
+            must_pay: 0,
+        })
+        .collect();
+
+    // The distribution members are credited with the refund
+    let credits: Vec<DistributionByMemberInput> = match refund_to_add.distribution {
+        None => {
+            let mut amounts_to_pay = Vec::<u128>::new();
+            for original_member in original_expense.members.iter() {
+                amounts_to_pay.push(calculate_amount_to_pay_by_member(
+                    original_expense,
+                    original_member.clone(),
+                )?);
+            }
+            if amounts_to_pay
+                .iter()
+                .all(|amount_to_pay| *amount_to_pay == 0)
+            {
+                return Err(ContractError::RefundWithoutDistribution);
+            }
+
+            original_expense
+                .members
+                .iter()
+                .zip(split_amount_proportionally(
+                    refund_to_add.amount,
+                    &amounts_to_pay,
+                )?)
+                .map(|(original_member, value)| DistributionByMemberInput {
+                    member_address: original_member.address,
+                    value,
+                })
+                .collect()
+        }
+        Some(distribution) => {
+            let values = match distribution.distribution_type {
+                DistributionType::EQUALLY => split_amount_equally(
+                    refund_to_add.amount,
+                    distribution.distribution_by_members.len() as u128,
+                )?,
+                DistributionType::UNEQUALLY => distribution
+                    .distribution_by_members
+                    .iter()
+                    .map(|d| d.value)
+                    .collect(),
+            };
+
+            let mut credited_amount: u128 = 0;
+            for value in values.iter() {
+                credited_amount = credited_amount
+                    .checked_add(*value)
+                    .ok_or(ContractError::Overflow)?;
+            }
+            if credited_amount != refund_to_add.amount {
+                return Err(ContractError::RefundDistributionDoesNotMatchAmount);
+            }
+
+            distribution
+                .distribution_by_members
+                .iter()
+                .zip(values)
+                .map(|(d, value)| DistributionByMemberInput {
+                    member_address: d.member_address,
+                    value,
+                })
+                .collect()
+        }
+    };
+
+    for credit in credits {
+        match members
+            .iter_mut()
+            .find(|m| m.address == credit.member_address)
+        {
+            Some(member) => {
+                member.must_pay = member
+                    .must_pay
+                    .checked_add(credit.value)
+                    .ok_or(ContractError::Overflow)?;
+            }
+            None => members.push(ExpenseMember {
+                address: credit.member_address,
+                paid: 0,
+                must_pay: credit.value,
+            }),
+        }
+    }
+
+    Ok(Expense {
+        id: 0,
+        group_id: refund_to_add.group_id,
+        kind: ExpenseKind::REFUND {
+            original_expense_id: original_expense.id,
+        },
+        status: ExpenseStatus::ACTIVE,
+        created_by,
+        amount: refund_to_add.amount,
+        distribution_type: DistributionType::UNEQUALLY,
+        members,
+        items: Vec::new(),
+        shared_lines: Vec::new(),
+        category: original_expense.category.clone(),
+        created_at: 0,
+        settle_by: None,
+    })
+}
+
+/// Builds the expense of a spend from the group pot.
+/// The spend is taken from every contribution proportionally, and is recorded as paid by the contributors.
+/// The distribution members must pay the spend.
+/// Updates the pot balance and contributions.
+pub fn build_pot_spend_expense(
+    pot: &mut GroupPot,
+    created_by: AccountId,
+    spend: PotSpendInput,
+) -> Result<Expense, ContractError> {
+    if spend.amount == 0 {
+        return Err(ContractError::ExpenseAmountIsZero);
+    }
+    if spend.amount > pot.balance {
+        return Err(ContractError::PotBalanceIsNotEnough);
+    }
+
+    let contributed_amounts: Vec<u128> = pot.contributions.iter().map(|c| c.amount).collect();
+    let paid_amounts = split_amount_proportionally(spend.amount, &contributed_amounts)?;
+
+    let mut members = Vec::<ExpenseMember>::new();
+    for (contribution, paid) in pot.contributions.iter_mut().zip(paid_amounts) {
+        contribution.amount = contribution
+            .amount
+            .checked_sub(paid)
+            .ok_or(ContractError::Overflow)?;
+
+        if paid > 0 {
+            members.push(ExpenseMember {
+                address: contribution.address,
+                paid,
+                must_pay: 0,
+            });
+        }
+    }
+    pot.contributions.retain(|c| c.amount > 0);
+    pot.balance = pot
+        .balance
+        .checked_sub(spend.amount)
+        .ok_or(ContractError::Overflow)?;
+
+    let distribution_by_members = spend.distribution.distribution_by_members;
+    let values = match spend.distribution.distribution_type {
+        DistributionType::EQUALLY => {
+            split_amount_equally(spend.amount, distribution_by_members.len() as u128)?
+        }
+        DistributionType::UNEQUALLY => distribution_by_members.iter().map(|d| d.value).collect(),
+    };
+
+    let mut distributed_amount: u128 = 0;
+    for (distribution_by_member, value) in distribution_by_members.iter().zip(values) {
+        distributed_amount = distributed_amount
+            .checked_add(value)
+            .ok_or(ContractError::Overflow)?;
+
+        match members
+            .iter_mut()
+            .find(|m| m.address == distribution_by_member.member_address)
+        {
+            Some(member) => {
+                member.must_pay = member
+                    .must_pay
+                    .checked_add(value)
+                    .ok_or(ContractError::Overflow)?;
+            }
+            None => members.push(ExpenseMember {
+                address: distribution_by_member.member_address,
+                paid: 0,
+                must_pay: value,
+            }),
+        }
+    }
+    if distributed_amount != spend.amount {
+        return Err(ContractError::PotSpendDistributionDoesNotMatchAmount);
+    }
+
+    Ok(Expense {
+        id: 0,
+        group_id: spend.group_id,
+        kind: ExpenseKind::EXPENSE,
+        status: ExpenseStatus::ACTIVE,
+        created_by,
+        amount: spend.amount,
+        distribution_type: DistributionType::UNEQUALLY,
+        members,
+        items: Vec::new(),
+        shared_lines: Vec::new(),
+        category: spend.category,
+        created_at: 0,
+        settle_by: None,
+    })
+}
+
+/// Resolves the dispute of a group expense.
+/// Confirm: the expense is active again (and affects the balances if it was frozen).
+/// Amend: the expense is replaced by a new one with the same ID.
+/// Delete: the expense is kept in the history without effect on the balances.
+/// An expense with refunds cannot be amended or deleted before them.
+/// The budget period spending is only updated if the expense was added in the current period.
+/// Updates the group balances, stats and budget period spending, and removes the dispute.
+/// Checks the auto-settle thresholds of the members of a confirmed or amended expense.
+pub fn resolve_expense_dispute(
+    instance: &mut Splitmate,
+    group_id: u128,
+    expense_id: u32,
+    resolution: DisputeResolution,
+) -> BaseResult {
+    let mut group = get_group_by_id(instance, group_id)?;
+    let mut group_expenses = instance
+        .group_expenses
+        .get(group_id)
+        .unwrap_or(Vec::<Expense>::new());
+    let mut group_stats = instance
+        .group_stats
+        .get(group_id)
+        .unwrap_or(GroupStats::new(group_id));
+
+    let expense_index = group_expenses
+        .iter()
+        .position(|e| e.id == expense_id)
+        .ok_or(ContractError::ExpenseDoesNotExist)?;
+    let expense = group_expenses[expense_index].clone();
+
+    match resolution {
+        DisputeResolution::Confirm => {
+            if !expense.affects_balances() {
+                process_expense_debts(&mut group, &expense)?;
+            }
+            group_expenses[expense_index].status = ExpenseStatus::ACTIVE;
+        }
+        DisputeResolution::Amend(expense_to_add) => {
+            if expense.kind != ExpenseKind::EXPENSE || expense_to_add.group_id != group_id {
+                return Err(ContractError::ExpenseCannotBeAmended);
+            }
+            if has_active_refunds(&group_expenses, expense.id) {
+                return Err(ContractError::ExpenseHasRefunds);
+            }
+
+            if expense.affects_balances() {
+                revert_expense_debts(&mut group, &expense)?;
+            }
+            revert_group_stats(&mut group_stats, &expense)?;
+
+            let mut amended_expense = Expense::new(expense.id, expense.created_by, expense_to_add);
+            amended_expense.created_at = expense.created_at;
+            if amended_expense.settle_by.is_none() {
+                amended_expense.settle_by = expense.settle_by;
+            }
+            amended_expense.validate()?;
+            process_expense_debts(&mut group, &amended_expense)?;
+            update_group_stats(&mut group_stats, &amended_expense)?;
+            if is_in_current_budget_period(instance, &group, &expense) {
+                update_group_budget_period(instance, &mut group, expense.amount, true)?;
+                update_group_budget_period(instance, &mut group, amended_expense.amount, false)?;
+            }
+
+            group_expenses[expense_index] = amended_expense;
+        }
+        DisputeResolution::Delete => {
+            if has_active_refunds(&group_expenses, expense.id) {
+                return Err(ContractError::ExpenseHasRefunds);
+            }
+
+            if expense.affects_balances() {
+                revert_expense_debts(&mut group, &expense)?;
+            }
+            revert_group_stats(&mut group_stats, &expense)?;
+            if is_in_current_budget_period(instance, &group, &expense) {
+                match expense.kind {
+                    ExpenseKind::EXPENSE => {
+                        update_group_budget_period(instance, &mut group, expense.amount, true)?
+                    }
+                    ExpenseKind::REFUND { .. } => {
+                        update_group_budget_period(instance, &mut group, expense.amount, false)?
+                    }
+                    ExpenseKind::TRANSFER => (),
+                }
+            }
+
+            group_expenses[expense_index].status = ExpenseStatus::DELETED;
+        }
+    }
+
+    instance.groups.insert(group_id, &group);
+    instance.group_expenses.insert(group_id, &group_expenses);
+    instance.group_stats.insert(group_id, &group_stats);
+    instance.expense_disputes.remove((group_id, expense_id));
+
+    let resolved_expense = &group_expenses[expense_index];
+    if resolved_expense.status == ExpenseStatus::ACTIVE {
+        check_auto_settle_thresholds(instance, &[resolved_expense.clone()])?;
+    }
+
+    Ok(())
+}
+
+/// Gets the overdue debts of the group members.
+/// The pending debt of a member is attributed to the most recent expenses where the member incurred debts.
+/// The part attributed to expenses with a past settle-by deadline is overdue.
+pub fn get_group_overdue_debts(
+    instance: &Splitmate,
+    group: &Group,
+) -> Result<Vec<OverdueDebt>, ContractError> {
+    let now = instance.env().block_timestamp();
+    let group_expenses = instance
+        .group_expenses
+        .get(group.id)
+        .unwrap_or(Vec::<Expense>::new());
+
+    let mut overdue_debts = Vec::<OverdueDebt>::new();
+
+    for member in group.members.iter().filter(|m| m.debt_value > 0) {
+        let mut pending_debt = member.debt_value.unsigned_abs();
+        let mut overdue_amount: u128 = 0;
+        let mut overdue_since: Option<u64> = None;
+        let mut oldest_created_at = now;
+
+        for expense in group_expenses
+            .iter()
+            .rev()
+            .filter(|e| e.affects_balances() && !matches!(e.kind, ExpenseKind::REFUND { .. }))
+        {
+            if pending_debt == 0 {
+                break;
+            }
+
+            let expense_member = match expense.members.iter().find(|m| m.address == member.address)
+            {
+                Some(expense_member) => expense_member,
+                None => continue,
+            };
+            let amount_to_pay = calculate_amount_to_pay_by_member(expense, expense_member.clone())?;
+            if amount_to_pay <= expense_member.paid {
+                continue;
+            }
+
+            let incurred_debt = (amount_to_pay - expense_member.paid).min(pending_debt);
+            pending_debt -= incurred_debt;
+
+            match expense.settle_by {
+                Some(settle_by) if settle_by < now => {
+                    overdue_amount = overdue_amount
+                        .checked_add(incurred_debt)
+                        .ok_or(ContractError::Overflow)?;
+                    overdue_since = Some(match overdue_since {
+                        Some(since) => since.min(settle_by),
+                        None => settle_by,
+                    });
+                    oldest_created_at = oldest_created_at.min(expense.created_at);
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(overdue_since) = overdue_since {
+            overdue_debts.push(OverdueDebt {
+                member_account: member.address,
+                overdue_amount,
+                overdue_since,
+                age: now.saturating_sub(oldest_created_at),
+            });
+        }
+    }
+
+    Ok(overdue_debts)
+}
+
+/// Builds a loan from the borrower to the lender.
+/// Adds the interest to the principal and splits the amount due in equal instalments.
+pub fn build_loan(
+    instance: &Splitmate,
+    id: u32,
+    borrower: AccountId,
+    loan_to_add: LoanInput,
+) -> Result<Loan, ContractError> {
+    if loan_to_add.principal == 0 {
+        return Err(ContractError::LoanPrincipalIsZero);
+    }
+    if loan_to_add.instalments == 0 {
+        return Err(ContractError::LoanWithoutInstalments);
+    }
+    if loan_to_add.instalments > 1 && loan_to_add.instalment_period == 0 {
+        return Err(ContractError::LoanInstalmentPeriodIsZero);
+    }
+
+    let interest = loan_to_add
+        .principal
+        .checked_mul(loan_to_add.interest_bps as u128)
+        .ok_or(ContractError::Overflow)?
+        / LOAN_INTEREST_BPS_BASE;
+    let total_due = loan_to_add
+        .principal
+        .checked_add(interest)
+        .ok_or(ContractError::Overflow)?;
+
+    let mut instalments = Vec::<LoanInstalment>::new();
+    let mut due_at = loan_to_add.first_due_at;
+    for amount in split_amount_equally(total_due, loan_to_add.instalments as u128)? {
+        instalments.push(LoanInstalment {
+            due_at,
+            amount,
+            paid: 0,
+        });
+        due_at = due_at
+            .checked_add(loan_to_add.instalment_period)
+            .ok_or(ContractError::Overflow)?;
+    }
+
+    Ok(Loan {
+        id,
+        group_id: loan_to_add.group_id,
+        lender: loan_to_add.lender,
+        borrower,
+        principal: loan_to_add.principal,
+        interest_bps: loan_to_add.interest_bps,
+        total_due,
+        repaid: 0,
+        instalments,
+        created_at: instance.env().block_timestamp(),
+    })
+}
+
+/// Emits an event for every member of the expenses whose debt is above the member auto-settle threshold (if any).
+/// Each member is checked once, against the stored group balances.
+pub fn check_auto_settle_thresholds(instance: &Splitmate, expenses: &[Expense]) -> BaseResult {
+    let mut checked_members = Vec::<(u128, AccountId)>::new();
+    let mut groups = Vec::<Group>::new();
+
+    for (group_id, member_address) in expenses.iter().flat_map(|expense| {
+        expense
+            .members
+            .iter()
+            .map(move |expense_member| (expense.group_id, expense_member.address))
+    }) {
+        if checked_members.contains(&(group_id, member_address)) {
+            continue;
+        }
+        checked_members.push((group_id, member_address));
+
+        let threshold = match instance
+            .auto_settle_thresholds
+            .get((group_id, member_address))
+        {
+            Some(threshold) => threshold,
+            None => continue,
+        };
+
+        let group_index = match groups.iter().position(|g| g.id == group_id) {
+            Some(index) => index,
+            None => {
+                groups.push(get_group_by_id(instance, group_id)?);
+                groups.len() - 1
+            }
+        };
+        let group = &groups[group_index];
+
+        if let Some(member) = group.members.iter().find(|m| m.address == member_address) {
+            if member.debt_value > 0 && member.debt_value.unsigned_abs() > threshold {
+                instance.env().emit_event(AutoSettleRequired {
+                    group_id: group.id,
+                    member_account: member.address,
+                    debt_value: member.debt_value,
+                    threshold,
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Gets the debts to pay by a member in a group, according to the group debts distribution.
+pub fn get_member_debts_to_pay(
+    instance: &Splitmate,
+    group: &Group,
+    member_address: AccountId,
+) -> Result<GroupDebtsToPay, ContractError> {
+    let takers = build_group_distribution(instance, group)?
+        .into_iter()
+        .filter(|d| d.member_account == member_address)
+        .flat_map(|d| d.transfers)
+        .map(|transfer| DistributionByMemberInput {
+            member_address: transfer.member_account,
+            value: transfer.value,
+        })
+        .collect();
+
+    Ok(GroupDebtsToPay {
+        group_id: group.id,
+        takers,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::delegation::Delegation;
+    use crate::expense::{ExpenseItem, ExpenseSharedLine};
+    use ink::prelude::string::String;
+
+    fn member(seed: u8, debt_value: i128) -> GroupMember {
+        GroupMember {
+            address: AccountId::from([seed; 32]),
+            name: String::from("member"),
+            debt_value,
+            guest_id: None,
+        }
+    }
+
+    fn group(members: Vec<GroupMember>) -> Group {
+        Group::new(1, String::from("group"), members, AccountId::from([1; 32]))
+    }
+
+    fn expense(members: Vec<ExpenseMember>) -> Expense {
+        Expense {
+            id: 1,
+            group_id: 1,
+            kind: ExpenseKind::EXPENSE,
+            status: ExpenseStatus::ACTIVE,
+            created_by: AccountId::from([1; 32]),
+            amount: members.iter().map(|m| m.paid).sum(),
+            distribution_type: DistributionType::UNEQUALLY,
+            members,
+            items: Vec::<ExpenseItem>::new(),
+            shared_lines: Vec::<ExpenseSharedLine>::new(),
+            category: None,
+            created_at: 0,
+            settle_by: None,
+        }
+    }
+
+    #[test]
+    fn to_balance_accepts_the_largest_balance() {
+        assert_eq!(to_balance(i128::MAX as u128), Ok(i128::MAX));
+    }
+
+    #[test]
+    fn to_balance_rejects_amounts_over_the_largest_balance() {
+        assert_eq!(
+            to_balance(i128::MAX as u128 + 1),
+            Err(ContractError::AmountTooLarge)
+        );
+    }
+
+    #[test]
+    fn process_expense_debts_fails_on_debt_overflow() {
+        let mut group = group([member(1, i128::MIN), member(2, i128::MAX)].to_vec());
+        let expense = expense(
+            [
+                ExpenseMember {
+                    address: AccountId::from([1; 32]),
+                    paid: 1,
+                    must_pay: 0,
+                },
+                ExpenseMember {
+                    address: AccountId::from([2; 32]),
+                    paid: 0,
+                    must_pay: 1,
+                },
+            ]
+            .to_vec(),
+        );
+
+        assert_eq!(
+            process_expense_debts(&mut group, &expense),
+            Err(ContractError::Overflow)
+        );
+    }
+
+    #[test]
+    fn process_expense_debts_fails_on_unknown_member() {
+        let mut group = group([member(1, 0)].to_vec());
+        let expense = expense(
+            [ExpenseMember {
+                address: AccountId::from([2; 32]),
+                paid: 1,
+                must_pay: 1,
+            }]
+            .to_vec(),
+        );
+
+        assert_eq!(
+            process_expense_debts(&mut group, &expense),
+            Err(ContractError::ExpenseDistributionMemberIsNotInTheGroup)
+        );
+    }
+
+    #[test]
+    fn process_giver_debt_keeps_the_remaining_taker_balance() {
+        let mut takers = [member(3, -100)].to_vec();
+
+        let first_transfer = process_giver_debt(30, &mut takers).unwrap();
+        assert_eq!(first_transfer.member_account, AccountId::from([3; 32]));
+        assert_eq!(first_transfer.value, 30);
+        assert_eq!(takers, [member(3, -70)].to_vec());
+
+        let second_transfer = process_giver_debt(70, &mut takers).unwrap();
+        assert_eq!(second_transfer.member_account, AccountId::from([3; 32]));
+        assert_eq!(second_transfer.value, 70);
+        assert!(takers.is_empty());
+    }
+
+    #[ink::test]
+    fn build_group_distribution_pays_every_giver_to_a_larger_taker() {
+        let instance = Splitmate::new(AccountId::from([9; 32]));
+        let group = group([member(1, 30), member(2, 70), member(3, -100)].to_vec());
+
+        let distribution = build_group_distribution(&instance, &group).unwrap();
+
+        let transfers: Vec<(AccountId, AccountId, u128)> = distribution
+            .iter()
+            .flat_map(|d| {
+                d.transfers
+                    .iter()
+                    .map(move |t| (d.member_account, t.member_account, t.value))
+            })
+            .collect();
+        assert_eq!(
+            transfers,
+            [
+                (AccountId::from([1; 32]), AccountId::from([3; 32]), 30),
+                (AccountId::from([2; 32]), AccountId::from([3; 32]), 70),
+            ]
+            .to_vec()
+        );
+    }
+
+    #[test]
+    fn update_member_group_debt_fails_on_debt_overflow() {
+        let mut group = group([member(1, i128::MAX), member(2, i128::MIN)].to_vec());
+
+        assert_eq!(
+            update_member_group_debt(&mut group, AccountId::from([1; 32]), true, 1),
+            Err(ContractError::Overflow)
+        );
+        assert_eq!(
+            update_member_group_debt(&mut group, AccountId::from([2; 32]), false, 1),
+            Err(ContractError::Overflow)
+        );
+    }
+
+    #[test]
+    fn update_member_group_debt_fails_on_missing_member() {
+        let mut group = group([member(1, 0)].to_vec());
+
+        assert_eq!(
+            update_member_group_debt(&mut group, AccountId::from([2; 32]), true, 1),
+            Err(ContractError::MemberNotFound)
+        );
+    }
+
+    fn account(seed: u8) -> AccountId {
+        AccountId::from([seed; 32])
+    }
+
+    fn equal_item(amount: u128, members: &[u8]) -> ExpenseItem {
+        ExpenseItem {
+            name: String::from("item"),
+            amount,
+            distribution: DistributionInput {
+                distribution_type: DistributionType::EQUALLY,
+                distribution_by_members: members
+                    .iter()
+                    .map(|seed| DistributionByMemberInput {
+                        member_address: account(*seed),
+                        value: 0,
+                    })
+                    .collect(),
+            },
+        }
+    }
+
+    fn itemized_expense(
+        items: Vec<ExpenseItem>,
+        shared_lines: Vec<ExpenseSharedLine>,
+    ) -> ItemizedExpenseInput {
+        ItemizedExpenseInput {
+            group_id: 1,
+            amount: 0,
+            payer_address: account(3),
+            items,
+            shared_lines,
+            category: None,
+            settle_by: None,
+        }
+    }
+
+    #[test]
+    fn split_amount_equally_gives_the_remainder_to_the_first_parts() {
+        assert_eq!(split_amount_equally(10, 3), Ok([4, 3, 3].to_vec()));
+        assert_eq!(
+            split_amount_equally(u128::MAX, 2),
+            Ok([u128::MAX / 2 + 1, u128::MAX / 2].to_vec())
+        );
+    }
+
+    #[test]
+    fn split_amount_equally_fails_without_parts() {
+        assert_eq!(
+            split_amount_equally(10, 0),
+            Err(ContractError::ExpenseItemWithoutMembers)
+        );
+    }
+
+    #[test]
+    fn split_amount_proportionally_keeps_the_amount_and_skips_zero_weights() {
+        assert_eq!(
+            split_amount_proportionally(100, &[1, 1, 1]),
+            Ok([34, 33, 33].to_vec())
+        );
+        assert_eq!(
+            split_amount_proportionally(7, &[0, 1, 1]),
+            Ok([0, 4, 3].to_vec())
+        );
+        assert_eq!(split_amount_proportionally(0, &[0, 0]), Ok([0, 0].to_vec()));
+    }
+
+    #[test]
+    fn split_amount_proportionally_fails_without_weights() {
+        assert_eq!(
+            split_amount_proportionally(10, &[0, 0]),
+            Err(ContractError::SplitWithoutWeights)
+        );
+    }
+
+    #[test]
+    fn split_amount_proportionally_fails_on_overflow() {
+        assert_eq!(
+            split_amount_proportionally(u128::MAX, &[2, 1]),
+            Err(ContractError::Overflow)
+        );
+        assert_eq!(
+            split_amount_proportionally(1, &[u128::MAX, 1]),
+            Err(ContractError::Overflow)
+        );
+    }
+
+    #[test]
+    fn collapse_itemized_expense_distributes_shared_lines_by_item_totals() {
+        let expense = itemized_expense(
+            [equal_item(60, &[1, 2]), equal_item(40, &[1])].to_vec(),
+            [ExpenseSharedLine {
+                name: String::from("tip"),
+                amount: 10,
+            }]
+            .to_vec(),
+        );
+
+        let collapsed = collapse_itemized_expense(&expense).unwrap();
+
+        assert_eq!(
+            collapsed.distribution.distribution_type,
+            DistributionType::UNEQUALLY
+        );
+        assert_eq!(
+            collapsed.distribution.distribution_by_members,
+            [
+                DistributionByMemberInput {
+                    member_address: account(1),
+                    value: 77,
+                },
+                DistributionByMemberInput {
+                    member_address: account(2),
+                    value: 33,
+                },
+                DistributionByMemberInput {
+                    member_address: account(3),
+                    value: 0,
+                },
+            ]
+            .to_vec()
+        );
+    }
+
+    #[test]
+    fn collapse_itemized_expense_rejects_unequal_items_not_matching_the_amount() {
+        let mut item = equal_item(10, &[1, 2]);
+        item.distribution.distribution_type = DistributionType::UNEQUALLY;
+        item.distribution.distribution_by_members[0].value = 4;
+        item.distribution.distribution_by_members[1].value = 5;
+
+        assert_eq!(
+            collapse_itemized_expense(&itemized_expense([item].to_vec(), Vec::new())),
+            Err(ContractError::ExpenseItemDistributionDoesNotMatchAmount)
+        );
+    }
+
+    #[test]
+    fn collapse_itemized_expense_fails_on_overflow() {
+        let huge_shared_line = ExpenseSharedLine {
+            name: String::from("tax"),
+            amount: u128::MAX,
+        };
+        assert_eq!(
+            collapse_itemized_expense(&itemized_expense(
+                [equal_item(2, &[1])].to_vec(),
+                [huge_shared_line].to_vec()
+            )),
+            Err(ContractError::Overflow)
+        );
+        assert_eq!(
+            collapse_itemized_expense(&itemized_expense(
+                [equal_item(u128::MAX, &[1]), equal_item(1, &[2])].to_vec(),
+                Vec::new()
+            )),
+            Err(ContractError::Overflow)
+        );
+    }
+
+    #[test]
+    fn add_to_net_balance_nets_the_groups_of_a_counterpart() {
+        let mut net_balances = Vec::<MemberNetBalance>::new();
+
+        add_to_net_balance(&mut net_balances, account(2), 1, 30).unwrap();
+        add_to_net_balance(&mut net_balances, account(2), 2, -10).unwrap();
+        add_to_net_balance(&mut net_balances, account(2), 1, 5).unwrap();
+
+        assert_eq!(
+            net_balances,
+            [MemberNetBalance {
+                member_account: account(2),
+                net_value: 25,
+                balances_by_group: [
+                    GroupPairBalance {
+                        group_id: 1,
+                        value: 35,
+                    },
+                    GroupPairBalance {
+                        group_id: 2,
+                        value: -10,
+                    },
+                ]
+                .to_vec(),
+            }]
+            .to_vec()
+        );
+    }
+
+    #[test]
+    fn add_to_net_balance_fails_on_overflow() {
+        let mut net_balances = Vec::<MemberNetBalance>::new();
+        add_to_net_balance(&mut net_balances, account(2), 1, i128::MAX).unwrap();
+
+        assert_eq!(
+            add_to_net_balance(&mut net_balances, account(2), 2, 1),
+            Err(ContractError::Overflow)
+        );
+    }
+
+    #[test]
+    fn validate_debt_to_pay_checks_the_giver_and_taker_balances() {
+        let group = group([member(1, 50), member(2, -30), member(3, -20)].to_vec());
+        let debt = |seed: u8, value: u128| DistributionByMemberInput {
+            member_address: account(seed),
+            value,
+        };
+
+        assert_eq!(
+            validate_debt_to_pay(&group, account(1), &debt(2, 30)),
+            Ok(())
+        );
+        assert_eq!(
+            validate_debt_to_pay(&group, account(1), &debt(2, 31)),
+            Err(ContractError::SettlementExceedsTakerCredit)
+        );
+        assert_eq!(
+            validate_debt_to_pay(&group, account(3), &debt(2, 10)),
+            Err(ContractError::SettlementExceedsGiverDebt)
+        );
+        assert_eq!(
+            validate_debt_to_pay(&group, account(1), &debt(2, i128::MAX as u128 + 1)),
+            Err(ContractError::AmountTooLarge)
+        );
+        assert_eq!(
+            validate_debt_to_pay(&group, account(1), &debt(4, 10)),
+            Err(ContractError::MemberIsNotInTheGroup)
+        );
+    }
+
+    #[test]
+    fn apply_group_payment_moves_the_amount_between_balances() {
+        let mut group = group([member(1, 50), member(2, -50)].to_vec());
+
+        apply_group_payment(&mut group, account(1), account(2), 20).unwrap();
+
+        let balance = |seed: u8| {
+            group
+                .members
+                .iter()
+                .find(|m| m.address == account(seed))
+                .map(|m| m.debt_value)
+        };
+        assert_eq!(balance(1), Some(30));
+        assert_eq!(balance(2), Some(-30));
+    }
+
+    #[ink::test]
+    fn build_group_distribution_spreads_a_giver_between_takers() {
+        let instance = Splitmate::new(account(9));
+        let group = group([member(1, 100), member(2, -30), member(3, -70)].to_vec());
+
+        let distribution = build_group_distribution(&instance, &group).unwrap();
+
+        assert_eq!(distribution.len(), 1);
+        assert_eq!(distribution[0].member_account, account(1));
+        assert_eq!(
+            distribution[0].transfers,
+            [
+                GroupMemberDistributionTransfer {
+                    member_account: account(3),
+                    value: 70,
+                },
+                GroupMemberDistributionTransfer {
+                    member_account: account(2),
+                    value: 30,
+                },
+            ]
+            .to_vec()
+        );
+    }
+
+    #[ink::test]
+    fn build_group_distribution_is_empty_without_debts() {
+        let instance = Splitmate::new(account(9));
+        let group = group([member(1, 0), member(2, 0)].to_vec());
+
+        assert_eq!(build_group_distribution(&instance, &group), Ok(Vec::new()));
+    }
+
+    #[ink::test]
+    fn build_loan_splits_the_principal_and_interest_in_instalments() {
+        let instance = Splitmate::new(account(9));
+        let loan_input = LoanInput {
+            group_id: 1,
+            lender: account(2),
+            principal: 1_000,
+            interest_bps: 500,
+            instalments: 3,
+            first_due_at: 100,
+            instalment_period: 10,
+        };
+
+        let loan = build_loan(&instance, 1, account(1), loan_input).unwrap();
+
+        assert_eq!(loan.total_due, 1_050);
+        assert_eq!(
+            loan.instalments
+                .iter()
+                .map(|i| (i.due_at, i.amount))
+                .collect::<Vec<(u64, u128)>>(),
+            [(100, 350), (110, 350), (120, 350)].to_vec()
+        );
+    }
+
+    #[ink::test]
+    fn build_loan_fails_on_interest_overflow() {
+        let instance = Splitmate::new(account(9));
+        let loan_input = LoanInput {
+            group_id: 1,
+            lender: account(2),
+            principal: u128::MAX,
+            interest_bps: 1,
+            instalments: 1,
+            first_due_at: 0,
+            instalment_period: 0,
+        };
+
+        assert_eq!(
+            build_loan(&instance, 1, account(1), loan_input),
+            Err(ContractError::Overflow)
+        );
+    }
+
+    #[ink::test]
+    fn migrate_member_group_drops_the_group_from_the_old_address_delegations() {
+        let mut instance = Splitmate::new(account(9));
+        instance
+            .groups
+            .insert(1, &group([member(1, 0), member(2, 0)].to_vec()));
+        let delegation = |principal: u8, delegate: u8, group_ids: Vec<u128>| Delegation {
+            principal: account(principal),
+            delegate: account(delegate),
+            group_ids,
+            actions: [DelegatedAction::AddExpense].to_vec(),
+            expires_at: None,
+        };
+        for (principal, delegate, group_ids) in [
+            (1, 4, [1].to_vec()),
+            (1, 5, [1, 2].to_vec()),
+            (2, 1, [1].to_vec()),
+        ] {
+            instance.delegations.insert(
+                (account(principal), account(delegate)),
+                &delegation(principal, delegate, group_ids),
+            );
+            add_to_member_delegations(&mut instance, account(principal), account(delegate));
+        }
+        instance.signer_nonces.insert(account(1), &3);
+
+        migrate_member_group(&mut instance, 1, account(1), account(6)).unwrap();
+
+        assert_eq!(instance.delegations.get((account(1), account(4))), None);
+        assert_eq!(instance.delegations.get((account(2), account(1))), None);
+        assert_eq!(
+            instance.delegations.get((account(1), account(5))),
+            Some(delegation(1, 5, [2].to_vec()))
+        );
+        assert_eq!(
+            instance.member_delegations.get(account(1)),
+            Some([(account(1), account(5))].to_vec())
+        );
+        assert_eq!(instance.member_delegations.get(account(2)), None);
+        assert_eq!(instance.signer_nonces.get(account(6)), Some(3));
+    }
+}